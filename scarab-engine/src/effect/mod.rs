@@ -0,0 +1,202 @@
+use std::fmt::Debug;
+
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use crate::{
+    gameobject::entity::registry::RegisteredEntity,
+    types::physbox::{HasBox, PhysBox},
+};
+
+/// Selects which entities a [PendingEffect](crate::scene::PendingEffect) is applied to
+pub trait EffectTarget<E>: Debug {
+    /// Whether the effect should be applied to the target entity
+    fn can_target(&mut self, target: &E) -> bool;
+}
+
+impl<E: RegisteredEntity> EffectTarget<E> for PhysBox {
+    fn can_target(&mut self, target: &E) -> bool {
+        target.inner_entity().get_box().has_overlap(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Targets every entity whose box touches a circle, i.e. for explosions or auras
+pub struct CircleTarget {
+    /// The center of the circle
+    pub center: Point,
+    /// The radius of the circle
+    pub radius: Scalar,
+}
+
+impl CircleTarget {
+    /// Creates a new circular target area
+    pub fn new(center: Point, radius: Scalar) -> Self {
+        Self { center, radius }
+    }
+
+    /// Whether the box touches the circle, i.e. the point in the box closest to the center is within the radius
+    pub fn overlaps_box(&self, physbox: &PhysBox) -> bool {
+        physbox.distance_to_point(self.center) <= self.radius
+    }
+}
+
+impl<E: RegisteredEntity> EffectTarget<E> for CircleTarget {
+    fn can_target(&mut self, target: &E) -> bool {
+        self.overlaps_box(target.inner_entity().get_box())
+    }
+}
+
+#[derive(Debug)]
+/// Targets entities that both inner targets can target
+pub struct AndTarget<E> {
+    left: Box<dyn EffectTarget<E>>,
+    right: Box<dyn EffectTarget<E>>,
+}
+
+impl<E> AndTarget<E> {
+    /// Combines two targets
+    pub fn new(
+        left: impl EffectTarget<E> + 'static,
+        right: impl EffectTarget<E> + 'static,
+    ) -> Self {
+        Self {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+}
+
+impl<E: Debug> EffectTarget<E> for AndTarget<E> {
+    fn can_target(&mut self, target: &E) -> bool {
+        self.left.can_target(target) && self.right.can_target(target)
+    }
+}
+
+#[derive(Debug)]
+/// Targets entities that either inner target can target
+pub struct OrTarget<E> {
+    left: Box<dyn EffectTarget<E>>,
+    right: Box<dyn EffectTarget<E>>,
+}
+
+impl<E> OrTarget<E> {
+    /// Combines two targets
+    pub fn new(
+        left: impl EffectTarget<E> + 'static,
+        right: impl EffectTarget<E> + 'static,
+    ) -> Self {
+        Self {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+}
+
+impl<E: Debug> EffectTarget<E> for OrTarget<E> {
+    fn can_target(&mut self, target: &E) -> bool {
+        self.left.can_target(target) || self.right.can_target(target)
+    }
+}
+
+#[derive(Debug)]
+/// Targets entities that the inner target can't target
+pub struct NotTarget<E> {
+    inner: Box<dyn EffectTarget<E>>,
+}
+
+impl<E> NotTarget<E> {
+    /// Inverts the target
+    pub fn new(inner: impl EffectTarget<E> + 'static) -> Self {
+        Self {
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl<E: Debug> EffectTarget<E> for NotTarget<E> {
+    fn can_target(&mut self, target: &E) -> bool {
+        !self.inner.can_target(target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{test_utils::TestEntity, types::HasUuid};
+
+    #[derive(Debug)]
+    /// Targets only the "player" entities
+    struct IsPlayer(Vec<Uuid>);
+
+    impl EffectTarget<TestEntity> for IsPlayer {
+        fn can_target(&mut self, target: &TestEntity) -> bool {
+            self.0.contains(&target.uuid())
+        }
+    }
+
+    fn circle() -> CircleTarget {
+        CircleTarget::new([0.0, 0.0].into(), 5.0)
+    }
+
+    #[test]
+    fn circle_targets_box_inside() {
+        assert!(circle().overlaps_box(&PhysBox::new([-1.0, -1.0, 2.0, 2.0]).unwrap()));
+        // The circle is entirely inside the box
+        assert!(circle().overlaps_box(&PhysBox::new([-10.0, -10.0, 20.0, 20.0]).unwrap()));
+    }
+
+    #[test]
+    fn circle_targets_box_touching_edge() {
+        assert!(circle().overlaps_box(&PhysBox::new([5.0, -1.0, 2.0, 2.0]).unwrap()));
+        assert!(circle().overlaps_box(&PhysBox::new([-1.0, -7.0, 2.0, 2.0]).unwrap()));
+    }
+
+    #[test]
+    fn circle_doesnt_target_box_outside() {
+        assert!(!circle().overlaps_box(&PhysBox::new([5.1, -1.0, 2.0, 2.0]).unwrap()));
+        // Inside the circle's bounding square, but past the curve
+        assert!(!circle().overlaps_box(&PhysBox::new([4.0, 4.0, 2.0, 2.0]).unwrap()));
+    }
+
+    #[test]
+    fn circle_targets_box_by_nearest_corner() {
+        // The center (5, 5) is ~7.07 away, but the corner (3, 3) is ~4.24 away
+        assert!(circle().overlaps_box(&PhysBox::new([3.0, 3.0, 4.0, 4.0]).unwrap()));
+
+        let mut target = circle();
+        assert!(target.can_target(&TestEntity::at([3.0, 3.0, 4.0, 4.0])));
+        assert!(!target.can_target(&TestEntity::at([4.0, 4.0, 2.0, 2.0])));
+    }
+
+    #[test]
+    fn combinators_select_players_in_box() {
+        let player_inside = TestEntity::at([1.0, 1.0, 2.0, 2.0]);
+        let player_outside = TestEntity::at([20.0, 1.0, 2.0, 2.0]);
+        let enemy_inside = TestEntity::at([4.0, 4.0, 2.0, 2.0]);
+        let enemy_outside = TestEntity::at([20.0, 20.0, 2.0, 2.0]);
+        let area = PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap();
+        let players = || IsPlayer(vec![player_inside.uuid(), player_outside.uuid()]);
+
+        let mut and = AndTarget::new(area, players());
+        assert!(and.can_target(&player_inside));
+        assert!(!and.can_target(&player_outside));
+        assert!(!and.can_target(&enemy_inside));
+        assert!(!and.can_target(&enemy_outside));
+
+        let mut or = OrTarget::new(area, players());
+        assert!(or.can_target(&player_inside));
+        assert!(or.can_target(&player_outside));
+        assert!(or.can_target(&enemy_inside));
+        assert!(!or.can_target(&enemy_outside));
+
+        let mut enemies_in_box = AndTarget::new(area, NotTarget::new(players()));
+        assert!(!enemies_in_box.can_target(&player_inside));
+        assert!(!enemies_in_box.can_target(&player_outside));
+        assert!(enemies_in_box.can_target(&enemy_inside));
+        assert!(!enemies_in_box.can_target(&enemy_outside));
+    }
+}
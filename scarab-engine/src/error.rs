@@ -22,6 +22,9 @@ pub enum ScarabError {
     #[error("Attempted to register an entity with a pre-existing UUID: {0}")]
     /// Registering a new entity failed
     EntityRegistration(Uuid),
+    #[error("Maximum health must be greater than 0")]
+    /// Occurs when an invalid maximum health is set
+    MaxHealth,
     #[error(transparent)]
     /// I/O Errors
     IoError(#[from] std::io::Error),
@@ -31,6 +34,22 @@ pub enum ScarabError {
     #[error(transparent)]
     /// Error related to rendering/graphics,
     RenderingError(#[from] RenderError),
+    #[error("Save version mismatch: save has {found}, needs {expected}")]
+    /// Occurs when loading a save that was written with a different save format version
+    SaveVersion {
+        /// The version found in the save
+        found: String,
+        /// The version that was needed
+        expected: String,
+    },
+    #[cfg(feature = "persistence")]
+    #[error(transparent)]
+    /// Errors writing a save
+    SaveEncode(#[from] rmp_serde::encode::Error),
+    #[cfg(feature = "persistence")]
+    #[error(transparent)]
+    /// Errors reading a save
+    SaveDecode(#[from] rmp_serde::decode::Error),
 }
 
 /// A generic result type for physics operations
@@ -54,6 +73,32 @@ pub enum PhysicsError {
     #[error("Error indexing into 'field' with index {0}")]
     /// Occurs when there is no cell on the field with the given index
     FieldIndex(usize),
+    #[error("Entity {0} has a NaN or infinite position, size, or velocity")]
+    /// Occurs when floating-point errors have corrupted an entity's physics state
+    NonFiniteState(Uuid),
+    #[error("Time scale must be positive and finite")]
+    /// Occurs when an invalid time scale is set
+    TimeScale,
+    #[error("Friction must be positive")]
+    /// Occurs when an invalid friction is set
+    Friction,
+    #[error("Health regeneration must be positive")]
+    /// Occurs when an invalid health regeneration rate is set
+    Regen,
+    #[error("Fixed timesteps must be greater than 0")]
+    /// Occurs when running fixed updates with a non-positive timestep
+    FixedTimestep,
+    #[error("No solidity is mapped to tile {0}")]
+    /// Occurs when building a field from a tilemap that has a tile id with no solidity
+    UnknownTile(u8),
+    #[error("Field cells {a} and {b} overlap")]
+    /// Occurs when validating a field whose cells overlap, with the cells' indices in the given list
+    OverlappingCells {
+        /// The index of the first overlapping cell
+        a: usize,
+        /// The index of the second overlapping cell
+        b: usize,
+    },
 }
 
 /// A generic result type for rendering operations
@@ -73,6 +118,16 @@ pub enum RenderError {
     /// 'String' is the specific error message
     #[error("Could not load texture {0}: {1}")]
     CouldNotLoadTexture(PathBuf, String),
+    /// Occurs when a font can't be loaded for rendering text
+    /// 'String' is the specific error message
+    #[error("Could not load font {0}: {1}")]
+    CouldNotLoadFont(PathBuf, String),
+    /// Occurs when text fails to render
+    #[error("Could not render text: {0}")]
+    TextRendering(String),
+    /// Occurs when a sprite atlas doesn't have a region with the requested name
+    #[error("No region named '{0}' in the sprite atlas")]
+    UnknownAtlasRegion(String),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -80,8 +135,13 @@ pub enum RenderError {
 pub enum AnimationError {
     /// Occurs when creating a sprite animation and the number of frames requested
     /// would overflow the width/height of the spritesheet.
-    #[error("Requested number of frames ({0}) is too many for sprite sheet ({1})")]
-    TooManyFrames(usize, usize),
+    #[error("Requested number of frames ({requested}) is too many for sprite sheet ({max})")]
+    TooManyFrames {
+        /// The number of frames requested
+        requested: usize,
+        /// The number of frames that fit in the sprite sheet
+        max: usize,
+    },
     /// An ASM doesn't have an animation for the animation state
     #[error("No animation loaded for state {0}")]
     NoAnimationForState(String),
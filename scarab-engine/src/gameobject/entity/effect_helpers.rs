@@ -1,11 +1,17 @@
 use graphics::types::Scalar;
+use petgraph::graph::NodeIndex;
 use serde::{Deserialize, Serialize};
+use shapes::Point;
 
-use super::registry::RegisteredEntity;
+use super::registry::{EntityHandle, RegisteredEntity};
 use crate::{
-    gameobject::HasHealth,
-    scene::{PendingEffect, TargetsOthers},
-    types::physbox::PhysBox,
+    effect::{CircleTarget, EffectTarget},
+    gameobject::{field::Field, HasHealth},
+    scene::{GameTickArgs, PendingEffect, TargetsOthers},
+    types::{
+        physbox::{HasBox, PhysBox},
+        Velocity,
+    },
     ScarabResult,
 };
 
@@ -73,6 +79,59 @@ impl TryAction {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+/// An attack that an entity can use once every `cooldown_duration` seconds
+pub struct AbilitySlot {
+    /// The attack done when the ability is used
+    pub attack: BasicAttack,
+    /// How long (in seconds) the ability cools down after being used
+    pub cooldown_duration: f64,
+    /// Whether the ability should be used and whether it's ready
+    pub try_action: TryAction,
+}
+
+impl AbilitySlot {
+    /// Creates a ready ability that does `attack` and then cools down for `cooldown_duration` seconds
+    pub fn new(attack: BasicAttack, cooldown_duration: f64) -> Self {
+        Self {
+            attack,
+            cooldown_duration,
+            try_action: TryAction::default(),
+        }
+    }
+
+    /// If the ability is ready, marks it to be used on the next [AbilitySlot::try_use]
+    pub fn request(&mut self) {
+        self.try_action.maybe_set_doing();
+    }
+
+    /// Cools the ability down by the tick's `dt`, then if it was requested, queues the attack
+    /// on `target_area` and starts the cooldown. Should be called once per tick.
+    /// Returns whether the attack was queued
+    pub fn try_use<E: RegisteredEntity>(
+        &mut self,
+        source: EntityHandle,
+        target_area: PhysBox,
+        args: &mut GameTickArgs<E>,
+    ) -> bool {
+        self.try_action.cooldown.cool(args.dt);
+
+        let used = self
+            .try_action
+            .should_do(Cooldown::Cooling(self.cooldown_duration));
+        if used {
+            args.pending_attacks
+                .push(self.attack.into_pending_effect(source, target_area));
+        }
+        used
+    }
+
+    /// The fraction of the cooldown that's left, from 1 right after being used to 0 when ready
+    pub fn cooldown_fraction(&self) -> f64 {
+        f64::from(self.try_action.cooldown) / self.cooldown_duration
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
 /// Represents an attack that does a raw amount of damage on an entity
 pub struct BasicAttack {
@@ -88,19 +147,19 @@ impl BasicAttack {
     /// Transforms self into a pending effect so it can be applied on the next tick
     pub fn into_pending_effect<E: RegisteredEntity>(
         &self,
-        source_index: usize,
-        target_area: PhysBox,
+        source: EntityHandle,
+        target_area: impl EffectTarget<E> + 'static,
     ) -> PendingEffect<E> {
         PendingEffect {
-            source: Some((source_index, false).into()),
-            target_area,
+            source: Some((source, false).into()),
+            target_area: Box::new(target_area),
             effect: Box::new(*self),
         }
     }
 }
 
 impl<E: RegisteredEntity> TargetsOthers<E> for BasicAttack {
-    fn apply_effect(&mut self, target: &mut E) -> ScarabResult<bool> {
+    fn apply_effect(&mut self, target: &mut E, _dt: f64) -> ScarabResult<bool> {
         target
             .inner_entity_mut()
             .get_health_mut()
@@ -108,14 +167,254 @@ impl<E: RegisteredEntity> TargetsOthers<E> for BasicAttack {
         Ok(false)
     }
 
-    fn update_src(&mut self, _src: &mut E) -> ScarabResult<()> {
+    fn update_src(&mut self, _src: &mut E, _field: &Field) -> ScarabResult<()> {
         Ok(())
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+/// Represents an attack whose damage falls off linearly from its center to the edge of its radius
+pub struct FalloffAttack {
+    max_damage: Scalar,
+    min_damage: Scalar,
+    center: Point,
+    radius: Scalar,
+}
+
+impl FalloffAttack {
+    /// Does `max_damage` at `center`, falling off to `min_damage` at `radius` away
+    pub fn new(max_damage: Scalar, min_damage: Scalar, center: Point, radius: Scalar) -> Self {
+        Self {
+            max_damage,
+            min_damage,
+            center,
+            radius,
+        }
+    }
+
+    /// The damage done to a target whose center is at `target_center`
+    pub fn damage_at(&self, target_center: Point) -> Scalar {
+        let distance = f64::hypot(
+            target_center.x - self.center.x,
+            target_center.y - self.center.y,
+        );
+        let fraction = if self.radius > 0.0 {
+            (distance / self.radius).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        self.max_damage + (self.min_damage - self.max_damage) * fraction
+    }
+
+    /// Transforms self into a pending effect targeting everything within its radius
+    pub fn into_pending_effect<E: RegisteredEntity>(
+        &self,
+        source: EntityHandle,
+    ) -> PendingEffect<E> {
+        PendingEffect {
+            source: Some((source, false).into()),
+            target_area: Box::new(CircleTarget::new(self.center, self.radius)),
+            effect: Box::new(*self),
+        }
+    }
+}
+
+impl<E: RegisteredEntity> TargetsOthers<E> for FalloffAttack {
+    fn apply_effect(&mut self, target: &mut E, _dt: f64) -> ScarabResult<bool> {
+        let target_center = target.inner_entity().get_box().center();
+        target
+            .inner_entity_mut()
+            .get_health_mut()
+            .raw_damage(self.damage_at(target_center));
+        Ok(false)
+    }
+
+    fn update_src(&mut self, _src: &mut E, _field: &Field) -> ScarabResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+/// Represents a lasting effect that does damage every tick until its duration runs out.
+/// The duration counts down once per tick, so every target in the area takes the same total damage
+pub struct DamageOverTime {
+    dps: Scalar,
+    remaining: f64,
+}
+
+impl DamageOverTime {
+    /// Does `dps` damage per second for `duration` seconds
+    pub fn new(dps: Scalar, duration: f64) -> Self {
+        Self {
+            dps,
+            remaining: duration,
+        }
+    }
+
+    /// The time (in seconds) left before the effect runs out
+    pub fn remaining(&self) -> f64 {
+        self.remaining
+    }
+}
+
+impl<E: RegisteredEntity> TargetsOthers<E> for DamageOverTime {
+    fn apply_effect(&mut self, target: &mut E, dt: f64) -> ScarabResult<bool> {
+        // Don't overshoot the total damage on the last tick
+        target
+            .inner_entity_mut()
+            .get_health_mut()
+            .raw_damage(self.dps * f64::min(dt, self.remaining));
+        Ok(self.remaining > dt)
+    }
+
+    fn update_src(&mut self, _src: &mut E, _field: &Field) -> ScarabResult<()> {
+        Ok(())
+    }
+
+    fn end_tick(&mut self, dt: f64) {
+        self.remaining -= f64::min(dt, self.remaining);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+/// Knocks targets directly away from a source position.
+/// The target's new velocity is still limited by its max velocity
+pub struct Knockback {
+    impulse: Scalar,
+    source_pos: Point,
+}
+
+impl Knockback {
+    /// Knocks targets away from `source_pos` with a speed of `impulse`
+    pub fn new(impulse: Scalar, source_pos: Point) -> Self {
+        Self {
+            impulse,
+            source_pos,
+        }
+    }
+}
+
+impl<E: RegisteredEntity> TargetsOthers<E> for Knockback {
+    fn apply_effect(&mut self, target: &mut E, _dt: f64) -> ScarabResult<bool> {
+        let target_center = target.inner_entity().get_box().center();
+        let direction = Velocity::from([
+            target_center.x - self.source_pos.x,
+            target_center.y - self.source_pos.y,
+        ])
+        .normalize();
+        target
+            .inner_entity_mut()
+            .set_velocity(direction * self.impulse);
+        Ok(false)
+    }
+
+    fn update_src(&mut self, _src: &mut E, _field: &Field) -> ScarabResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Steers its source toward the target along a path of cells on the field, so that it
+/// walks around walls instead of straight into them. Lasts for as long as there is a target,
+/// so its target area should only select a single entity. It's dropped once its source is despawned.
+/// The path is only recomputed when the target moves into a different cell or the source leaves it
+pub struct PathFollow {
+    speed: Scalar,
+    target_pos: Option<Point>,
+    target_cell: Option<NodeIndex>,
+    path: Vec<NodeIndex>,
+}
+
+impl PathFollow {
+    /// Moves the source at `speed` toward the target
+    pub fn new(speed: Scalar) -> Self {
+        Self {
+            speed,
+            target_pos: None,
+            target_cell: None,
+            path: Vec::new(),
+        }
+    }
+
+    /// The cells left on the path, starting with the one the source is in
+    pub fn path(&self) -> &[NodeIndex] {
+        &self.path
+    }
+
+    /// Transforms self into a pending effect following whatever is in the target area
+    pub fn into_pending_effect<E: RegisteredEntity>(
+        self,
+        source: EntityHandle,
+        target_area: impl EffectTarget<E> + 'static,
+    ) -> PendingEffect<E> {
+        PendingEffect {
+            source: Some((source, false).into()),
+            target_area: Box::new(target_area),
+            effect: Box::new(self),
+        }
+    }
+
+    /// The middle of the border shared by two neighboring boxes
+    fn border_midpoint(a: &PhysBox, b: &PhysBox) -> Point {
+        let left = f64::max(a.left_x(), b.left_x());
+        let right = f64::min(a.right_x(), b.right_x());
+        let top = f64::max(a.top_y(), b.top_y());
+        let bottom = f64::min(a.bottom_y(), b.bottom_y());
+        [(left + right) / 2.0, (top + bottom) / 2.0].into()
+    }
+}
+
+impl<E: RegisteredEntity> TargetsOthers<E> for PathFollow {
+    fn apply_effect(&mut self, target: &mut E, _dt: f64) -> ScarabResult<bool> {
+        self.target_pos = Some(target.inner_entity().get_box().center());
+        Ok(true)
+    }
+
+    fn update_src(&mut self, src: &mut E, field: &Field) -> ScarabResult<()> {
+        let Some(target_pos) = self.target_pos.take() else {
+            return Ok(());
+        };
+        let src_pos = src.inner_entity().get_box().center();
+        let target_cell = field.cell_at_pos(target_pos).map(|c| c.index());
+        let src_cell = field.cell_at_pos(src_pos).map(|c| c.index());
+
+        match src_cell.and_then(|c| self.path.iter().position(|p| *p == c)) {
+            Some(reached) if target_cell == self.target_cell => {
+                self.path.drain(..reached);
+            }
+            _ => {
+                self.path = field.find_path(src_pos, target_pos).unwrap_or_default();
+                self.target_cell = target_cell;
+            }
+        }
+
+        let waypoint = match self.path.as_slice() {
+            [] => src_pos,
+            [_] => target_pos,
+            [current, next, ..] => PathFollow::border_midpoint(
+                field.get_cell(*current)?.get_box(),
+                field.get_cell(*next)?.get_box(),
+            ),
+        };
+        let direction =
+            Velocity::from([waypoint.x - src_pos.x, waypoint.y - src_pos.y]).normalize();
+        src.inner_entity_mut().set_velocity(direction * self.speed);
+
+        Ok(())
+    }
+
+    fn needs_source(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use uuid::Uuid;
+
     use super::*;
+    use crate::test_utils::TestEntity;
 
     #[test]
     fn try_action_sets_doing_when_ready() {
@@ -179,6 +478,43 @@ mod test {
         assert_eq!(try_action.cooldown, Cooldown::Cooling(5.0));
     }
 
+    #[test]
+    fn ability_slot_queues_once_per_cooldown() {
+        let field = Field::new(vec![]).unwrap();
+        let mut pending_attacks = Vec::new();
+        let mut spawn_queue = Vec::new();
+        let mut args = GameTickArgs::<TestEntity> {
+            field: &field,
+            pending_attacks: &mut pending_attacks,
+            spawn_queue: &mut spawn_queue,
+            dt: 0.5,
+        };
+        let target_area = PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap();
+        let mut ability = AbilitySlot::new(BasicAttack::new(3.0), 1.0);
+        let source = EntityHandle {
+            index: 0,
+            generation: 0,
+            uuid: Uuid::new_v4(),
+        };
+
+        // Nothing happens until the ability is requested
+        assert!(!ability.try_use(source, target_area, &mut args));
+        ability.request();
+        assert!(ability.try_use(source, target_area, &mut args));
+        assert_eq!(ability.cooldown_fraction(), 1.0);
+
+        // Requests while cooling down are dropped
+        ability.request();
+        assert!(!ability.try_use(source, target_area, &mut args));
+        assert_eq!(ability.cooldown_fraction(), 0.5);
+        assert!(!ability.try_use(source, target_area, &mut args));
+        assert_eq!(args.pending_attacks.len(), 1);
+
+        ability.request();
+        assert!(ability.try_use(source, target_area, &mut args));
+        assert_eq!(args.pending_attacks.len(), 2);
+    }
+
     #[test]
     fn cooldown_cool_reduces_remaining_time() {
         let start = 5.0;
@@ -208,4 +544,102 @@ mod test {
         cooldown.cool(5.0);
         assert_eq!(cooldown, Cooldown::Ready);
     }
+
+    #[test]
+    fn falloff_attack_scales_with_distance() {
+        let attack = FalloffAttack::new(10.0, 2.0, [0.0, 0.0].into(), 8.0);
+
+        assert_eq!(attack.damage_at([0.0, 0.0].into()), 10.0);
+        assert_eq!(attack.damage_at([0.0, 8.0].into()), 2.0);
+        assert_eq!(attack.damage_at([4.0, 0.0].into()), 6.0);
+        // Past the rim is still the minimum
+        assert_eq!(attack.damage_at([20.0, 0.0].into()), 2.0);
+    }
+
+    #[test]
+    fn falloff_attack_damages_by_target_center() {
+        let mut attack = FalloffAttack::new(10.0, 2.0, [0.0, 0.0].into(), 8.0);
+        let mut target = TestEntity::at([3.0, -1.0, 2.0, 2.0]);
+        let max = target.entity.get_health().max();
+
+        attack.apply_effect(&mut target, 0.1).unwrap();
+        assert_eq!(target.entity.get_health().current(), max - 6.0);
+    }
+
+    #[test]
+    fn damage_over_time_lasts_for_duration() {
+        let mut dot = DamageOverTime::new(4.0, 1.0);
+        let mut target = TestEntity::at([0.0, 0.0, 2.0, 2.0]);
+        let max = target.entity.get_health().max();
+
+        let mut ticks = 0;
+        while dot.apply_effect(&mut target, 0.3).unwrap() {
+            TargetsOthers::<TestEntity>::end_tick(&mut dot, 0.3);
+            ticks += 1;
+        }
+        TargetsOthers::<TestEntity>::end_tick(&mut dot, 0.3);
+        assert_eq!(ticks, 3);
+        assert!(dot.remaining() <= 0.0);
+
+        let damage = max - target.entity.get_health().current();
+        assert!((damage - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn knockback_pushes_away_from_source() {
+        let mut knockback = Knockback::new(5.0, [0.0, 0.0].into());
+
+        let mut right = TestEntity::at([9.0, -1.0, 2.0, 2.0]);
+        right.entity.set_max_velocity(100.0).unwrap();
+        knockback.apply_effect(&mut right, 0.1).unwrap();
+        assert_eq!(right.entity.get_velocity(), [5.0, 0.0].into());
+
+        // Up is negative y
+        let mut above = TestEntity::at([-1.0, -11.0, 2.0, 2.0]);
+        above.entity.set_max_velocity(100.0).unwrap();
+        knockback.apply_effect(&mut above, 0.1).unwrap();
+        assert_eq!(above.entity.get_velocity(), [0.0, -5.0].into());
+    }
+
+    #[test]
+    fn knockback_is_clamped_by_max_velocity() {
+        let mut knockback = Knockback::new(50.0, [0.0, 0.0].into());
+        let mut target = TestEntity::at([9.0, -1.0, 2.0, 2.0]);
+        target.entity.set_max_velocity(10.0).unwrap();
+
+        knockback.apply_effect(&mut target, 0.1).unwrap();
+        assert_eq!(target.entity.get_velocity(), [10.0, 0.0].into());
+    }
+
+    #[test]
+    fn path_follow_heads_around_walls() {
+        use std::collections::HashMap;
+
+        use crate::{
+            gameobject::{NO_SOLIDITY, SOLID},
+            types::physbox::HasBoxMut,
+        };
+
+        // The target is on the other side of a wall, so the way around is up and over it
+        let tiles: [&[u8]; 3] = [&[0, 0, 0], &[0, 1, 0], &[0, 1, 0]];
+        let solidity_map = HashMap::from([(0, NO_SOLIDITY), (1, SOLID)]);
+        let field = Field::from_grid(&tiles, [10.0, 10.0].into(), &solidity_map).unwrap();
+
+        let mut follow = PathFollow::new(5.0);
+        let mut src = TestEntity::at([4.0, 24.0, 2.0, 2.0]);
+        src.entity.set_max_velocity(100.0).unwrap();
+        let mut target = TestEntity::at([24.0, 24.0, 2.0, 2.0]);
+
+        assert!(follow.apply_effect(&mut target, 0.1).unwrap());
+        follow.update_src(&mut src, &field).unwrap();
+        assert_eq!(follow.path().len(), 7);
+        assert_eq!(src.entity.get_velocity(), [0.0, -5.0].into());
+
+        // Once the source reaches the next cell it heads for the one after
+        src.entity.get_box_mut().set_pos([4.0, 14.0].into());
+        follow.apply_effect(&mut target, 0.1).unwrap();
+        follow.update_src(&mut src, &field).unwrap();
+        assert_eq!(follow.path().len(), 6);
+        assert_eq!(src.entity.get_velocity(), [0.0, -5.0].into());
+    }
 }
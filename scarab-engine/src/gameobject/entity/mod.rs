@@ -5,6 +5,7 @@ use graphics::{
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
+use shapes::{Point, Size};
 use uuid::Uuid;
 
 use super::{
@@ -17,7 +18,7 @@ use crate::{
     scene::GameTickArgs,
     types::{
         physbox::{HasBox, HasBoxMut, PhysBox},
-        Velocity,
+        Axis, BoxEdge, HasVelocity, Velocity,
     },
     PhysicsError, PhysicsResult, ScarabResult,
 };
@@ -28,6 +29,9 @@ pub mod effect_helpers;
 /// Handles the registration of entities (loading and unloading)
 pub mod registry;
 
+/// Speeds below this are snapped to zero by friction so that entities come to a full stop
+const FRICTION_EPSILON: Scalar = 1e-3;
+
 /// A trait for game objects that wrap/own an entity
 pub trait HasEntity {
     /// Returns a reference to the game object's inner entity
@@ -42,22 +46,134 @@ pub trait HasEntity {
 pub struct Entity {
     velocity: Velocity,
     max_velocity: Scalar,
+    /// How the maximum velocity limits the velocity
+    #[serde(default)]
+    velocity_clamp: VelocityClamp,
+    /// The change in velocity per second, applied before moving each tick
+    #[serde(default)]
+    acceleration: Velocity,
+    /// The rate at which velocity decays toward zero each second, applied before moving each tick
+    #[serde(default)]
+    friction: Scalar,
+    /// The health restored each second, applied each tick
+    #[serde(default)]
+    regen_per_second: Scalar,
     physbox: PhysBox,
+    /// The box's position at the start of the last tick, for [interpolating](Entity::interpolated_box) between ticks
+    #[serde(skip)]
+    prev_pos: Option<Point>,
+    /// Extra boxes that move with the entity (i.e. hitboxes), positioned relative to the entity's box
+    #[serde(default)]
+    attachments: Vec<PhysBox>,
     health: Health,
     solidity: Solidity,
     uuid: Uuid,
+    /// The layers this entity is on, as a bitset
+    #[serde(default = "all_collision_layers")]
+    collision_layer: u32,
+    /// The layers this entity collides with, as a bitset
+    #[serde(default = "all_collision_layers")]
+    collision_mask: u32,
+    /// The remaining time (in seconds) that this entity can drop through platforms
+    #[serde(default)]
+    drop_through_remaining: f64,
+}
+
+/// Entities are on, and collide with, every layer unless told otherwise
+fn all_collision_layers() -> u32 {
+    u32::MAX
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// The edges of an entity that were stopped by cells during a single tick of movement
+pub struct CollisionReport {
+    /// Each edge that was stopped, without repeats
+    pub edges: Vec<BoxEdge>,
+}
+
+impl CollisionReport {
+    /// Records that the given edge was stopped
+    pub fn add_edge(&mut self, edge: BoxEdge) {
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// Whether the given edge was stopped
+    pub fn hit_edge(&self, edge: BoxEdge) -> bool {
+        self.edges.contains(&edge)
+    }
+
+    /// Whether no edges were stopped
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How an entity's maximum velocity limits its velocity
+pub enum VelocityClamp {
+    /// The velocity's magnitude can't go above the maximum, so moving diagonally is no faster than along an axis
+    #[default]
+    Magnitude,
+    /// Each component of the velocity is limited to the maximum on its own,
+    /// so one axis being at the maximum doesn't slow the other
+    PerAxis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The baseline parameters for new entities, so a game can define them once.
+/// See [Entity::with_defaults]
+pub struct EntityDefaults {
+    /// The entity's maximum velocity. Must be greater than or equal to 0
+    pub max_velocity: Scalar,
+    /// The size of the entity's box. The width and height must each be greater than 0
+    pub size: Size,
+    /// The entity's maximum (and starting) health
+    pub max_health: Scalar,
+    /// The entity's solidity
+    pub solidity: Solidity,
+}
+
+impl Default for EntityDefaults {
+    fn default() -> Self {
+        Self {
+            max_velocity: 1.0,
+            size: [1.0, 1.0].into(),
+            max_health: 10.0,
+            solidity: SOLID,
+        }
+    }
 }
 
 impl Entity {
     /// Creates an Entity with default settings
     pub fn new() -> ScarabResult<Self> {
+        Self::with_defaults(EntityDefaults::default())
+    }
+
+    /// Creates an Entity at the origin with the given defaults
+    pub fn with_defaults(defaults: EntityDefaults) -> ScarabResult<Self> {
+        if defaults.max_velocity < 0.0 {
+            return Err(PhysicsError::MaxVelocity.into());
+        }
+
         Ok(Self {
             velocity: [0.0, 0.0].into(),
-            max_velocity: 1.0,
-            physbox: PhysBox::new([0.0, 0.0, 1.0, 1.0].into())?,
-            health: Health::new(10.0),
-            solidity: SOLID,
+            max_velocity: defaults.max_velocity,
+            velocity_clamp: VelocityClamp::default(),
+            acceleration: [0.0, 0.0].into(),
+            friction: 0.0,
+            regen_per_second: 0.0,
+            physbox: PhysBox::new([0.0, 0.0, defaults.size.w, defaults.size.h])?,
+            prev_pos: None,
+            attachments: Vec::new(),
+            health: Health::new(defaults.max_health),
+            solidity: defaults.solidity,
             uuid: Uuid::new_v4(),
+            collision_layer: all_collision_layers(),
+            collision_mask: all_collision_layers(),
+            drop_through_remaining: 0.0,
         })
     }
 
@@ -66,20 +182,139 @@ impl Entity {
         self.uuid
     }
 
-    /// Sets the entity's velocity, limited by its maximum velocity
+    /// Sets the entity's velocity, limited by its maximum velocity according to its [VelocityClamp]
     pub fn set_velocity(&mut self, velocity: Velocity) {
-        self.velocity = if velocity.magnitude_sq() <= self.max_velocity * self.max_velocity {
-            velocity
-        } else {
-            velocity.normalize() * self.max_velocity
+        self.velocity = match self.velocity_clamp {
+            VelocityClamp::Magnitude => {
+                if velocity.magnitude_sq() <= self.max_velocity * self.max_velocity {
+                    velocity
+                } else {
+                    velocity.normalize() * self.max_velocity
+                }
+            }
+            VelocityClamp::PerAxis => [
+                velocity.x.clamp(-self.max_velocity, self.max_velocity),
+                velocity.y.clamp(-self.max_velocity, self.max_velocity),
+            ]
+            .into(),
         }
     }
 
+    /// Sets how the entity's maximum velocity limits its velocity.
+    /// Takes effect the next time the velocity is set
+    pub fn set_velocity_clamp(&mut self, velocity_clamp: VelocityClamp) {
+        self.velocity_clamp = velocity_clamp;
+    }
+
+    /// Gets how the entity's maximum velocity limits its velocity
+    pub fn get_velocity_clamp(&self) -> VelocityClamp {
+        self.velocity_clamp
+    }
+
+    /// Sets only the x component of the entity's velocity, limited by its maximum velocity
+    pub fn set_velocity_x(&mut self, x: Scalar) {
+        self.set_velocity([x, self.velocity.y].into())
+    }
+
+    /// Sets only the y component of the entity's velocity (i.e. for a jump), limited by its maximum velocity
+    pub fn set_velocity_y(&mut self, y: Scalar) {
+        self.set_velocity([self.velocity.x, y].into())
+    }
+
+    /// Adds `delta` to the entity's velocity, limiting the sum by its maximum velocity
+    pub fn add_velocity(&mut self, delta: Velocity) {
+        self.set_velocity(self.velocity + delta)
+    }
+
     /// Gets the entity's current velocity
     pub fn get_velocity(&self) -> Velocity {
         self.velocity
     }
 
+    /// Sets the entity's acceleration (i.e. gravity), which changes its velocity every tick
+    pub fn set_acceleration(&mut self, acceleration: Velocity) {
+        self.acceleration = acceleration;
+    }
+
+    /// Gets the entity's acceleration
+    pub fn get_acceleration(&self) -> Velocity {
+        self.acceleration
+    }
+
+    /// Sets the entity's friction. Must be greater than or equal to 0.
+    /// Each tick the velocity is scaled by `e^(-friction * dt)`, so a friction of 0 never slows the entity down
+    pub fn set_friction(&mut self, friction: Scalar) -> PhysicsResult<()> {
+        if friction < 0.0 {
+            return Err(PhysicsError::Friction);
+        }
+        self.friction = friction;
+
+        Ok(())
+    }
+
+    /// Gets the entity's friction
+    pub fn get_friction(&self) -> Scalar {
+        self.friction
+    }
+
+    /// Sets the health the entity regenerates each second. Must be greater than or equal to 0
+    pub fn set_regen_per_second(&mut self, regen_per_second: Scalar) -> PhysicsResult<()> {
+        if regen_per_second < 0.0 {
+            return Err(PhysicsError::Regen);
+        }
+        self.regen_per_second = regen_per_second;
+
+        Ok(())
+    }
+
+    /// Gets the health the entity regenerates each second
+    pub fn get_regen_per_second(&self) -> Scalar {
+        self.regen_per_second
+    }
+
+    /// The entity's position at the start of its last tick. `None` if it hasn't ticked yet
+    pub fn previous_pos(&self) -> Option<Point> {
+        self.prev_pos
+    }
+
+    /// The entity's box `alpha` of the way from where it was at the start of its last tick to where it is now,
+    /// i.e. with the [Scene::interpolation_alpha](crate::scene::Scene::interpolation_alpha)
+    /// to smooth out rendering between fixed steps
+    pub fn interpolated_box(&self, alpha: Scalar) -> PhysBox {
+        let mut physbox = self.physbox;
+        if let Some(prev) = self.prev_pos {
+            let curr = *self.physbox.pos();
+            physbox.set_pos(prev + (curr - prev) * alpha);
+        }
+        physbox
+    }
+
+    /// Sets the bitset of layers the entity is on
+    pub fn set_collision_layer(&mut self, layer: u32) {
+        self.collision_layer = layer;
+    }
+
+    /// Gets the bitset of layers the entity is on
+    pub fn get_collision_layer(&self) -> u32 {
+        self.collision_layer
+    }
+
+    /// Sets the bitset of layers the entity collides with
+    pub fn set_collision_mask(&mut self, mask: u32) {
+        self.collision_mask = mask;
+    }
+
+    /// Gets the bitset of layers the entity collides with
+    pub fn get_collision_mask(&self) -> u32 {
+        self.collision_mask
+    }
+
+    /// Whether this entity and `other` should collide, i.e. each one's mask includes one of the other's layers
+    pub fn collides_with(&self, other: &Entity) -> bool {
+        self.collision_mask & other.collision_layer != 0
+            && other.collision_mask & self.collision_layer != 0
+    }
+
     /// Sets the entity's maximum velocity. Must be greater than or equal to 0
     pub fn set_max_velocity(&mut self, max_velocity: Scalar) -> PhysicsResult<()> {
         if max_velocity < 0.0 {
@@ -95,6 +330,39 @@ impl Entity {
         self.max_velocity
     }
 
+    /// Checks that the entity's position, size, and velocity are all finite (not NaN or infinite)
+    pub fn validate_state(&self) -> PhysicsResult<()> {
+        let pos = self.physbox.pos();
+        let size = self.physbox.size();
+        let values = [
+            pos.x,
+            pos.y,
+            size.w,
+            size.h,
+            self.velocity.x,
+            self.velocity.y,
+            self.acceleration.x,
+            self.acceleration.y,
+        ];
+
+        if values.iter().all(|v| v.is_finite()) {
+            Ok(())
+        } else {
+            Err(PhysicsError::NonFiniteState(self.uuid))
+        }
+    }
+
+    /// Lets the entity fall through any [drop-through](Cell::is_drop_through) cells for the next `window` seconds.
+    /// i.e. while the player is holding "down" on a one-way platform
+    pub fn drop_through(&mut self, window: f64) {
+        self.drop_through_remaining = window;
+    }
+
+    /// Whether the entity is currently able to fall through drop-through cells
+    pub fn is_dropping_through(&self) -> bool {
+        self.drop_through_remaining > 0.0
+    }
+
     /// Get the position of the entity after its next movement assuming no collisions
     pub fn get_projected_box(&self) -> PhysBox {
         let mut physbox = self.physbox.clone();
@@ -102,18 +370,226 @@ impl Entity {
         physbox
     }
 
-    /// Returns a callback function for resolving entity-entity collisions
-    pub fn game_tick<E>(&mut self, args: &GameTickArgs<E>) -> PhysicsResult<()> {
+    /// Creates a box in world coordinates that is offset from the entity's position.
+    /// i.e. the area of a sword swing in front of the entity
+    /// Fails if the size has a width or height that are <= 0
+    pub fn local_box(&self, offset: Point, size: Size) -> PhysicsResult<PhysBox> {
+        let pos = self.physbox.pos();
+        PhysBox::new([pos.x + offset.x, pos.y + offset.y, size.w, size.h])
+    }
+
+    /// Attaches a box of `size` at `offset` from the entity's position that moves along with the entity,
+    /// i.e. a hitbox that's larger than the entity. Returns the attachment's index.
+    /// Fails if the size has a width or height that are <= 0
+    pub fn add_attachment(&mut self, offset: Point, size: Size) -> PhysicsResult<usize> {
+        self.attachments
+            .push(PhysBox::new([offset.x, offset.y, size.w, size.h])?);
+        Ok(self.attachments.len() - 1)
+    }
+
+    /// Removes the attachment at the given index, returning it relative to the entity's position.
+    /// Later attachments shift down an index
+    pub fn remove_attachment(&mut self, i: usize) -> Option<PhysBox> {
+        (i < self.attachments.len()).then(|| self.attachments.remove(i))
+    }
+
+    /// Removes all of the entity's attachments
+    pub fn clear_attachments(&mut self) {
+        self.attachments.clear();
+    }
+
+    /// The entity's attachments in world coordinates, in index order
+    pub fn attachment_boxes(&self) -> impl Iterator<Item = PhysBox> + '_ {
+        let pos = *self.physbox.pos();
+        self.attachments.iter().map(move |local| {
+            let mut physbox = *local;
+            physbox.set_pos(pos + *local.pos());
+            physbox
+        })
+    }
+
+    /// Whether the entity's box or any of its attachments overlap `area`
+    pub fn any_box_overlaps(&self, area: &PhysBox) -> bool {
+        self.physbox.has_overlap(area) || self.attachment_boxes().any(|b| b.has_overlap(area))
+    }
+
+    /// Moves the entity for this tick.
+    /// Returns the edges of the entity that were stopped by cells while moving
+    pub fn game_tick<E>(&mut self, args: &GameTickArgs<E>) -> PhysicsResult<CollisionReport> {
+        self.regenerate(args.dt);
         self.try_move(args.field, args.dt)
     }
 
+    /// Heals the entity by its regeneration over `dt` seconds. Dead entities stay dead
+    fn regenerate(&mut self, dt: f64) {
+        if self.regen_per_second > 0.0 && !self.health.is_dead() {
+            self.health.heal(self.regen_per_second * dt);
+        }
+    }
+
     /// Attempts to move this entity according to its velocity until it collides
     /// with any cells
-    fn try_move(&mut self, field: &Field, dt: f64) -> PhysicsResult<()> {
+    fn try_move(&mut self, field: &Field, dt: f64) -> PhysicsResult<CollisionReport> {
+        self.prev_pos = Some(*self.physbox.pos());
+        let dropping_through = self.is_dropping_through();
+        self.drop_through_remaining = f64::max(self.drop_through_remaining - dt, 0.0);
+
+        if self.acceleration != [0.0, 0.0].into() {
+            self.set_velocity(self.velocity + self.acceleration * dt);
+        }
+        if self.friction > 0.0 {
+            let decayed = self.velocity * f64::exp(-self.friction * dt);
+            // Exponential decay never reaches zero on its own
+            self.velocity = if decayed.magnitude_sq() < FRICTION_EPSILON * FRICTION_EPSILON {
+                [0.0, 0.0].into()
+            } else {
+                decayed
+            };
+        }
+
+        let mut report = CollisionReport::default();
         if self.velocity == [0.0, 0.0].into() {
-            return Ok(());
+            return Ok(report);
+        }
+
+        // Moving and resolving one axis at a time keeps the movement along one axis
+        // from being stopped by the seams between cells on the other. It also means
+        // that hitting a wall at an angle only stops the movement into the wall,
+        // so the entity keeps sliding along it
+        for axis in [Axis::X, Axis::Y] {
+            let mut axis_velocity: Velocity = [0.0, 0.0].into();
+            axis.set_component_of_velocity(
+                &mut axis_velocity,
+                axis.component_of_velocity(&self.velocity),
+            );
+            if axis_velocity == [0.0, 0.0].into() {
+                continue;
+            }
+
+            // Only moving up to the first cell that would be hit keeps fast entities
+            // from skipping over thin cells entirely
+            match self.earliest_impact(field, axis_velocity, dt, dropping_through) {
+                Some((edge, fraction, cell_box)) => {
+                    let mut new_box = self.resolve_movement(
+                        field,
+                        axis_velocity,
+                        dt * fraction,
+                        dropping_through,
+                        &mut report,
+                    )?;
+                    // Floating-point error can leave the box just short of the cell
+                    new_box.set_touching_opposite_edge(&cell_box, edge);
+                    report.add_edge(edge);
+                    self.physbox = new_box;
+                }
+                None => {
+                    self.physbox = self.resolve_movement(
+                        field,
+                        axis_velocity,
+                        dt,
+                        dropping_through,
+                        &mut report,
+                    )?;
+                }
+            }
         }
 
+        // TODO: switch to a separate "resolve entity collisions step"
+        // doing these collated will definite cause problems as the number
+        // of entities increases
+        Ok(report)
+    }
+
+    /// Finds the first cell this entity can't enter that it would run into while moving
+    /// with its current velocity for `dt`.
+    /// Returns the entity's edge that would hit the cell and the fraction of `dt` before
+    /// the impact. Cells the entity already overlaps aren't hit, and pushing into a cell the
+    /// entity is resting against hits it immediately
+    pub fn swept_collision(&self, field: &Field, dt: f64) -> Option<(BoxEdge, f64)> {
+        self.earliest_impact(field, self.velocity, dt, self.is_dropping_through())
+            .map(|(edge, fraction, _)| (edge, fraction))
+    }
+
+    /// The edge, fraction of `dt` and cell box of the first impact when moving with the
+    /// given velocity. See [Entity::swept_collision]
+    fn earliest_impact(
+        &self,
+        field: &Field,
+        velocity: Velocity,
+        dt: f64,
+        dropping_through: bool,
+    ) -> Option<(BoxEdge, f64, PhysBox)> {
+        let from = *self.physbox.pos();
+        let to = from + velocity * dt;
+        let size = *self.physbox.size();
+
+        // Only cells in the area the box passes through can be run into
+        let swept = self.physbox.swept_region(velocity * dt);
+        field
+            .cells_overlapping_box(&swept)
+            .into_iter()
+            .filter_map(|cell| {
+                // Sweeping the entity's position against the cell grown by the entity's
+                // size is the same as sweeping the entity's box against the cell
+                let cell_box = *cell.get_box();
+                let padded = PhysBox::new([
+                    cell_box.left_x() - size.w,
+                    cell_box.top_y() - size.h,
+                    cell_box.size().w + size.w,
+                    cell_box.size().h + size.h,
+                ])
+                .ok()?;
+                // Cells the entity is already inside of can't be run into
+                if cell_box.has_overlap(&self.physbox) {
+                    return None;
+                }
+                let fraction = padded.segment_entry(from, to)?;
+
+                // The edge that hits is on the axis that enters the cell last
+                let entry_along = |axis: Axis| {
+                    let start = axis.component_of_point(&from);
+                    let delta = axis.component_of_point(&to) - start;
+                    if delta > 0.0 {
+                        (padded.get_near_axis(axis) - start) / delta
+                    } else if delta < 0.0 {
+                        (padded.get_far_axis(axis) - start) / delta
+                    } else {
+                        f64::NEG_INFINITY
+                    }
+                };
+                let edge = if entry_along(Axis::X) >= entry_along(Axis::Y) {
+                    if velocity.x > 0.0 {
+                        BoxEdge::Right
+                    } else {
+                        BoxEdge::Left
+                    }
+                } else if velocity.y > 0.0 {
+                    BoxEdge::Bottom
+                } else {
+                    BoxEdge::Top
+                };
+
+                let dropped_through =
+                    dropping_through && edge == BoxEdge::Bottom && cell.is_drop_through();
+                if dropped_through || cell.get_solidity().enter_edge(edge.opposite()) {
+                    return None;
+                }
+
+                Some((edge, fraction, cell_box))
+            })
+            .min_by(|(_, a, _), (_, b, _)| a.total_cmp(b))
+    }
+
+    /// Gives the entity's box after moving with the given velocity, stopping at any
+    /// cell edges that can't be crossed
+    fn resolve_movement(
+        &self,
+        field: &Field,
+        velocity: Velocity,
+        dt: f64,
+        dropping_through: bool,
+        report: &mut CollisionReport,
+    ) -> PhysicsResult<PhysBox> {
         // TODO: having to recalculate the current cell every time will get time intensive
         // Should create a new function to take into account the old current cell and its neighbors
         // at the very least only going through those. Even more so, we can add the edges that were
@@ -124,7 +600,7 @@ impl Entity {
         let current_cell_overlaps =
             field.neighbors_of_cell_overlapping_box(current_cell, &self.physbox)?;
 
-        let new_pos = *self.physbox.pos() + self.velocity * dt;
+        let new_pos = *self.physbox.pos() + velocity * dt;
         let mut new_box = self.physbox.clone();
         new_box.set_pos(new_pos);
 
@@ -136,11 +612,18 @@ impl Entity {
 
                 for (edge, neighbors_on_edge) in from_cells_neighbors.iter_by_edge() {
                     for neighbor in neighbors_on_edge {
+                        // Platforms that can be dropped through are only ever entered from the top
+                        if dropping_through && edge == BoxEdge::Bottom && neighbor.is_drop_through()
+                        {
+                            continue;
+                        }
+
                         if (!from_this_cell.get_solidity().exit_edge(edge)
                             || !neighbor.get_solidity().enter_edge(edge.opposite()))
-                            && self.velocity.is_reduced_by_edge(edge)
+                            && velocity.is_reduced_by_edge(edge)
                         {
                             new_box.set_touching_edge(&from_this_cell.get_box(), edge);
+                            report.add_edge(edge);
                         }
                     }
 
@@ -149,10 +632,11 @@ impl Entity {
                     // because at present, movement is not defined when the entity is not
                     // fully contained by some number of cells
                     if neighbors_on_edge.len() == 0
-                        && self.velocity.is_reduced_by_edge(edge)
+                        && velocity.is_reduced_by_edge(edge)
                         && from_this_cell.get_box().is_edge_crossed_by(&new_box, edge)
                     {
                         new_box.set_touching_edge(&from_this_cell.get_box(), edge);
+                        report.add_edge(edge);
                     }
                 }
 
@@ -166,12 +650,7 @@ impl Entity {
             }
         }
 
-        self.physbox = new_box;
-
-        // TODO: switch to a separate "resolve entity collisions step"
-        // doing these collated will definite cause problems as the number
-        // of entities increases
-        Ok(())
+        Ok(new_box)
     }
 }
 
@@ -203,6 +682,17 @@ impl HasSolidity for Entity {
     }
 }
 
+impl HasVelocity for Entity {
+    fn get_velocity(&self) -> Velocity {
+        Entity::get_velocity(self)
+    }
+
+    /// Sets the entity's velocity, limited by its maximum velocity
+    fn set_velocity(&mut self, velocity: Velocity) {
+        Entity::set_velocity(self, velocity)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Renders an entity by filling its PhysBox with the set color
 pub struct EntityView {
@@ -234,7 +724,11 @@ impl View for EntityView {
 mod test {
     use super::*;
 
-    use crate::PhysicsError;
+    use crate::{
+        gameobject::{ENTER_TOP, NO_SOLIDITY},
+        types::ROOT_2,
+        PhysicsError,
+    };
 
     // Doing a lot of square roots with the vector math propogates the floating-point error
     // a bunch, this is just to make sure it's reasonably accurate
@@ -270,4 +764,569 @@ mod test {
         entity.set_velocity(velocity);
         assert_eq!(entity.velocity, velocity);
     }
+
+    /// An open column with a drop-through platform in the middle and a solid floor at the bottom
+    fn create_platform_field() -> Field {
+        Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 20.0]).unwrap()),
+            Cell::new(!ENTER_TOP, PhysBox::new([0.0, 20.0, 10.0, 2.0]).unwrap())
+                .with_drop_through(true),
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 22.0, 10.0, 20.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 42.0, 10.0, 5.0]).unwrap()),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn drop_through_falls_through_platform_onto_solid_below() {
+        let field = create_platform_field();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+        entity.get_box_mut().set_pos([2.0, 10.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        entity.set_velocity([0.0, 5.0].into());
+
+        // Lands on the platform and stays there
+        for _ in 0..5 {
+            entity.try_move(&field, 1.0).unwrap();
+        }
+        assert_eq!(entity.get_box().bottom_y(), 20.0);
+
+        // Falls through the platform and lands on the floor
+        entity.drop_through(1.0);
+        assert!(entity.is_dropping_through());
+        for _ in 0..10 {
+            entity.try_move(&field, 1.0).unwrap();
+        }
+        assert!(!entity.is_dropping_through());
+        assert_eq!(entity.get_box().bottom_y(), 42.0);
+    }
+
+    /// A row of open cells above a floor made of separate solid cells
+    fn create_tiled_floor_field() -> Field {
+        let mut cells = Vec::new();
+        for i in 0..5 {
+            let x = i as f64 * 10.0;
+            cells.push(Cell::new(
+                NO_SOLIDITY,
+                PhysBox::new([x, 0.0, 10.0, 20.0]).unwrap(),
+            ));
+            cells.push(Cell::new(
+                SOLID,
+                PhysBox::new([x, 20.0, 10.0, 10.0]).unwrap(),
+            ));
+        }
+        Field::new(cells).unwrap()
+    }
+
+    #[test]
+    fn sliding_along_tiled_floor_passes_seams() {
+        let field = create_tiled_floor_field();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+        entity.get_box_mut().set_pos([1.0, 16.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        // Pressing into the floor while moving right
+        entity.set_velocity([3.0, 4.0].into());
+
+        for i in 1..=10 {
+            entity.try_move(&field, 1.0).unwrap();
+            assert_eq!(entity.get_box().left_x(), 1.0 + 3.0 * i as f64);
+            assert_eq!(entity.get_box().bottom_y(), 20.0);
+        }
+    }
+
+    #[test]
+    fn local_box_is_offset_from_entity() {
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([10.0, 20.0].into());
+
+        let local = entity
+            .local_box([5.0, -2.0].into(), [3.0, 4.0].into())
+            .unwrap();
+        assert_eq!(local, PhysBox::new([15.0, 18.0, 3.0, 4.0]).unwrap());
+
+        assert_eq!(
+            entity.local_box([0.0, 0.0].into(), [0.0, 4.0].into()),
+            Err(PhysicsError::PhysBoxSize)
+        );
+    }
+
+    /// A column of open cells beside a wall made of separate solid cells
+    fn create_tiled_wall_field() -> Field {
+        let mut cells = Vec::new();
+        for i in 0..5 {
+            let y = i as f64 * 10.0;
+            cells.push(Cell::new(
+                NO_SOLIDITY,
+                PhysBox::new([0.0, y, 20.0, 10.0]).unwrap(),
+            ));
+            cells.push(Cell::new(
+                SOLID,
+                PhysBox::new([20.0, y, 10.0, 10.0]).unwrap(),
+            ));
+        }
+        Field::new(cells).unwrap()
+    }
+
+    #[test]
+    fn moving_diagonally_into_wall_slides_along_it() {
+        let field = create_tiled_wall_field();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+        entity.get_box_mut().set_pos([14.0, 1.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        entity.set_velocity([3.0, 4.0].into());
+
+        // Hits the wall on the first move but keeps all of its vertical movement
+        entity.try_move(&field, 1.0).unwrap();
+        assert_eq!(entity.get_box().right_x(), 20.0);
+        assert_eq!(entity.get_box().top_y(), 5.0);
+
+        for i in 1..=8 {
+            entity.try_move(&field, 1.0).unwrap();
+            assert_eq!(entity.get_box().right_x(), 20.0);
+            assert_eq!(entity.get_box().top_y(), 5.0 + 4.0 * i as f64);
+        }
+    }
+
+    #[test]
+    fn moving_diagonally_into_corner_settles_flush() {
+        // An open room with walls to the right and below
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 20.0, 20.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([20.0, 0.0, 10.0, 30.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 20.0, 20.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+        entity.get_box_mut().set_pos([10.0, 12.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+
+        for _ in 0..5 {
+            entity.set_velocity([3.0, 4.0].into());
+            entity.try_move(&field, 1.0).unwrap();
+            // Each axis stops at its own wall without pushing the entity back along the other
+            assert!(entity.get_box().right_x() <= 20.0);
+            assert!(entity.get_box().bottom_y() <= 20.0);
+        }
+        assert_eq!(entity.get_box().right_x(), 20.0);
+        assert_eq!(entity.get_box().bottom_y(), 20.0);
+    }
+
+    #[test]
+    fn movement_matches_on_field_loaded_without_view() {
+        let field = create_tiled_floor_field();
+        let headless: Field =
+            serde_json::from_str(&serde_json::to_string(&field).unwrap()).unwrap();
+
+        let mut on_full = Entity::new().unwrap();
+        on_full.set_max_velocity(5.0).unwrap();
+        on_full.get_box_mut().set_pos([1.0, 10.0].into());
+        on_full.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        let mut on_headless = Entity::new().unwrap();
+        on_headless.set_max_velocity(5.0).unwrap();
+        on_headless.get_box_mut().set_pos([1.0, 10.0].into());
+        on_headless
+            .get_box_mut()
+            .set_size([4.0, 4.0].into())
+            .unwrap();
+
+        for velocity in [[3.0, 4.0], [5.0, 0.0], [-3.0, 4.0], [0.0, -5.0]] {
+            on_full.set_velocity(velocity.into());
+            on_headless.set_velocity(velocity.into());
+            for _ in 0..3 {
+                on_full.try_move(&field, 1.0).unwrap();
+                on_headless.try_move(&headless, 1.0).unwrap();
+                assert_eq!(on_full.get_box(), on_headless.get_box());
+            }
+        }
+    }
+
+    #[test]
+    fn with_defaults_uses_all_defaults() {
+        let defaults = EntityDefaults {
+            max_velocity: 12.0,
+            size: [3.0, 5.0].into(),
+            max_health: 40.0,
+            solidity: ENTER_TOP,
+        };
+        let entity = Entity::with_defaults(defaults).unwrap();
+
+        assert_eq!(entity.get_max_velocity(), 12.0);
+        assert_eq!(
+            entity.get_box(),
+            &PhysBox::new([0.0, 0.0, 3.0, 5.0]).unwrap()
+        );
+        assert_eq!(entity.get_health().max(), 40.0);
+        assert_eq!(entity.get_health().current(), 40.0);
+        assert_eq!(entity.get_solidity(), &ENTER_TOP);
+        assert_eq!(entity.get_velocity(), [0.0, 0.0].into());
+    }
+
+    #[test]
+    fn with_defaults_rejects_invalid_defaults() {
+        let negative_velocity = EntityDefaults {
+            max_velocity: -1.0,
+            ..Default::default()
+        };
+        assert!(Entity::with_defaults(negative_velocity).is_err());
+
+        let no_size = EntityDefaults {
+            size: [0.0, 1.0].into(),
+            ..Default::default()
+        };
+        assert!(Entity::with_defaults(no_size).is_err());
+    }
+
+    #[test]
+    fn validate_state_catches_nan_velocity() {
+        let mut entity = Entity::new().unwrap();
+        assert_eq!(entity.validate_state(), Ok(()));
+
+        entity.velocity = [f64::NAN, 0.0].into();
+        assert_eq!(
+            entity.validate_state(),
+            Err(PhysicsError::NonFiniteState(entity.uuid()))
+        );
+    }
+
+    #[test]
+    fn validate_state_catches_infinite_position() {
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([0.0, f64::INFINITY].into());
+        assert_eq!(
+            entity.validate_state(),
+            Err(PhysicsError::NonFiniteState(entity.uuid()))
+        );
+    }
+
+    /// An open cell on either side of a 1-unit thick solid wall
+    fn create_thin_wall_field() -> Field {
+        Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 20.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([50.0, 0.0, 1.0, 20.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([51.0, 0.0, 50.0, 20.0]).unwrap()),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn fast_entity_stops_at_thin_wall() {
+        let field = create_thin_wall_field();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(1000.0).unwrap();
+        entity.get_box_mut().set_pos([40.0, 8.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        entity.set_velocity([1000.0, 0.0].into());
+
+        assert_eq!(
+            entity.swept_collision(&field, 0.1),
+            Some((BoxEdge::Right, 0.06))
+        );
+        entity.try_move(&field, 0.1).unwrap();
+        assert_eq!(entity.get_box().right_x(), 50.0);
+
+        // Resting against the wall now, so it stays put
+        assert_eq!(
+            entity.swept_collision(&field, 0.1),
+            Some((BoxEdge::Right, 0.0))
+        );
+        entity.try_move(&field, 0.1).unwrap();
+        assert_eq!(entity.get_box().right_x(), 50.0);
+    }
+
+    #[test]
+    fn fast_entity_stops_at_thin_wall_past_many_cells() {
+        // A long corridor of small open cells leading up to the wall
+        let mut cells: Vec<Cell> = (0..100)
+            .map(|i| {
+                Cell::new(
+                    NO_SOLIDITY,
+                    PhysBox::new([i as f64 * 10.0, 0.0, 10.0, 20.0]).unwrap(),
+                )
+            })
+            .collect();
+        cells.push(Cell::new(
+            SOLID,
+            PhysBox::new([1000.0, 0.0, 1.0, 20.0]).unwrap(),
+        ));
+        cells.push(Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([1001.0, 0.0, 100.0, 20.0]).unwrap(),
+        ));
+        let field = Field::new(cells).unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(2000.0).unwrap();
+        entity.get_box_mut().set_pos([100.0, 8.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        entity.set_velocity([2000.0, 0.0].into());
+
+        let (edge, fraction) = entity.swept_collision(&field, 0.5).unwrap();
+        assert_eq!(edge, BoxEdge::Right);
+        assert!((fraction - 0.896).abs() < 1e-9);
+        entity.try_move(&field, 0.5).unwrap();
+        assert_eq!(entity.get_box().right_x(), 1000.0);
+    }
+
+    #[test]
+    fn fast_falling_entity_lands_on_thin_floor() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 20.0, 50.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 50.0, 20.0, 1.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 51.0, 20.0, 50.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(1000.0).unwrap();
+        entity.get_box_mut().set_pos([8.0, 10.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        entity.set_velocity([0.0, 1000.0].into());
+
+        entity.try_move(&field, 1.0).unwrap();
+        assert_eq!(entity.get_box().bottom_y(), 50.0);
+        assert_eq!(entity.get_box().left_x(), 8.0);
+    }
+
+    #[test]
+    fn landing_on_floor_reports_bottom_edge() {
+        let field = create_tiled_floor_field();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+        entity.get_box_mut().set_pos([1.0, 10.0].into());
+        entity.get_box_mut().set_size([4.0, 4.0].into()).unwrap();
+        entity.set_velocity([3.0, 4.0].into());
+
+        let report = entity.try_move(&field, 1.0).unwrap();
+        assert!(report.is_empty());
+
+        let report = entity.try_move(&field, 1.0).unwrap();
+        assert_eq!(report.edges, vec![BoxEdge::Bottom]);
+        assert!(report.hit_edge(BoxEdge::Bottom));
+        assert!(!report.hit_edge(BoxEdge::Right));
+    }
+
+    #[test]
+    fn acceleration_grows_velocity_up_to_max() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(10.0).unwrap();
+        entity.get_box_mut().set_pos([10.0, 0.0].into());
+        entity.set_acceleration([0.0, 2.0].into());
+
+        for i in 1..=5 {
+            entity.try_move(&field, 1.0).unwrap();
+            assert_eq!(entity.get_velocity(), [0.0, 2.0 * i as f64].into());
+        }
+        for _ in 0..3 {
+            entity.try_move(&field, 1.0).unwrap();
+            assert_eq!(entity.get_velocity(), [0.0, 10.0].into());
+        }
+        // 2 + 4 + 6 + 8 + 10 + 3 * 10
+        assert_eq!(entity.get_box().top_y(), 60.0);
+    }
+
+    #[test]
+    fn friction_decays_velocity_to_zero() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 1000.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(10.0).unwrap();
+        entity.set_friction(5.0).unwrap();
+        entity.set_velocity([10.0, 0.0].into());
+
+        let mut last_speed = 10.0;
+        for _ in 0..5 {
+            entity.try_move(&field, 0.1).unwrap();
+            let speed = entity.get_velocity().magnitude();
+            assert!(speed < last_speed);
+            last_speed = speed;
+        }
+        for _ in 0..100 {
+            entity.try_move(&field, 0.1).unwrap();
+        }
+        assert_eq!(entity.get_velocity(), [0.0, 0.0].into());
+        assert_eq!(entity.set_friction(-1.0), Err(PhysicsError::Friction));
+    }
+
+    #[test]
+    fn health_regenerates_up_to_max() {
+        let mut entity = Entity::with_defaults(EntityDefaults {
+            max_health: 10.0,
+            ..Default::default()
+        })
+        .unwrap();
+        entity.set_regen_per_second(2.0).unwrap();
+        entity.get_health_mut().raw_damage(5.0);
+
+        for _ in 0..10 {
+            entity.regenerate(0.1);
+        }
+        assert!((entity.get_health().current() - 7.0).abs() < EPSILON);
+
+        for _ in 0..20 {
+            entity.regenerate(0.1);
+        }
+        assert_eq!(entity.get_health().current(), 10.0);
+
+        entity.get_health_mut().raw_damage(10.0);
+        entity.regenerate(1.0);
+        assert!(entity.get_health().is_dead());
+        assert_eq!(entity.set_regen_per_second(-1.0), Err(PhysicsError::Regen));
+    }
+
+    #[test]
+    fn attachments_move_with_entity() {
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([10.0, 20.0].into());
+        let sword = entity
+            .add_attachment([1.0, -4.0].into(), [6.0, 2.0].into())
+            .unwrap();
+        assert_eq!(
+            entity.add_attachment([0.0, 0.0].into(), [0.0, 1.0].into()),
+            Err(PhysicsError::PhysBoxSize)
+        );
+        assert_eq!(
+            entity.attachment_boxes().collect::<Vec<_>>(),
+            vec![PhysBox::new([11.0, 16.0, 6.0, 2.0]).unwrap()]
+        );
+
+        entity.get_box_mut().set_pos([50.0, 0.0].into());
+        assert_eq!(
+            entity.attachment_boxes().collect::<Vec<_>>(),
+            vec![PhysBox::new([51.0, -4.0, 6.0, 2.0]).unwrap()]
+        );
+
+        // Only the attachment reaches the area
+        let area = PhysBox::new([55.0, -10.0, 5.0, 7.0]).unwrap();
+        assert!(!entity.get_box().has_overlap(&area));
+        assert!(entity.any_box_overlaps(&area));
+
+        assert_eq!(
+            entity.remove_attachment(sword),
+            Some(PhysBox::new([1.0, -4.0, 6.0, 2.0]).unwrap())
+        );
+        assert_eq!(entity.remove_attachment(sword), None);
+        assert!(!entity.any_box_overlaps(&area));
+    }
+
+    #[test]
+    fn velocity_clamp_modes() {
+        let mut entity = Entity::new().unwrap();
+        assert_eq!(entity.get_velocity_clamp(), VelocityClamp::Magnitude);
+
+        entity.set_velocity([1.0, 1.0].into());
+        let Velocity { x, y } = entity.get_velocity();
+        assert!((x - 1.0 / *ROOT_2).abs() < EPSILON);
+        assert!((y - 1.0 / *ROOT_2).abs() < EPSILON);
+
+        entity.set_velocity_clamp(VelocityClamp::PerAxis);
+        entity.set_velocity([1.0, 1.0].into());
+        assert_eq!(entity.get_velocity(), [1.0, 1.0].into());
+        entity.set_velocity([3.0, -0.5].into());
+        assert_eq!(entity.get_velocity(), [1.0, -0.5].into());
+    }
+
+    #[test]
+    fn interpolated_box_lerps_across_last_tick() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 1000.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([10.0, 20.0].into());
+        // Nothing to interpolate from before the first tick
+        assert_eq!(entity.interpolated_box(0.0), *entity.get_box());
+
+        entity.set_max_velocity(10.0).unwrap();
+        entity.set_velocity([8.0, 4.0].into());
+        entity.try_move(&field, 0.5).unwrap();
+        assert_eq!(entity.previous_pos(), Some([10.0, 20.0].into()));
+
+        let previous = entity.interpolated_box(0.0);
+        assert_eq!(*previous.pos(), [10.0, 20.0].into());
+        assert_eq!(previous.size(), entity.get_box().size());
+        assert_eq!(entity.interpolated_box(1.0), *entity.get_box());
+        assert_eq!(*entity.interpolated_box(0.5).pos(), [12.0, 21.0].into());
+    }
+
+    #[test]
+    fn zero_friction_keeps_velocity() {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([0.0, 0.0, 1000.0, 100.0]).unwrap(),
+        )])
+        .unwrap();
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(10.0).unwrap();
+        entity.set_velocity([10.0, 0.0].into());
+
+        for _ in 0..10 {
+            entity.try_move(&field, 0.1).unwrap();
+            assert_eq!(entity.get_velocity(), [10.0, 0.0].into());
+        }
+    }
+
+    #[test]
+    fn acceleration_round_trips_through_serde() {
+        let mut entity = Entity::new().unwrap();
+        entity.set_acceleration([1.0, -2.0].into());
+
+        let json = serde_json::to_string(&entity).unwrap();
+        let loaded: Entity = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_acceleration(), [1.0, -2.0].into());
+    }
+
+    #[test]
+    fn has_velocity_respects_max_velocity() {
+        fn push_right<T: HasVelocity>(object: &mut T, speed: Scalar) {
+            let velocity = object.get_velocity();
+            object.set_velocity(velocity + [speed, 0.0]);
+        }
+
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+
+        push_right(&mut entity, 3.0);
+        assert_eq!(HasVelocity::get_velocity(&entity), [3.0, 0.0].into());
+        push_right(&mut entity, 3.0);
+        assert_eq!(HasVelocity::get_velocity(&entity), [5.0, 0.0].into());
+    }
+
+    #[test]
+    fn velocity_component_setters_are_clamped() {
+        let mut entity = Entity::new().unwrap();
+        entity.set_max_velocity(5.0).unwrap();
+        entity.set_velocity([4.0, 0.0].into());
+
+        // Jumping keeps the horizontal speed while it fits under the max
+        entity.set_velocity_y(-3.0);
+        assert_eq!(entity.get_velocity(), [4.0, -3.0].into());
+        assert_eq!(entity.get_velocity().magnitude(), 5.0);
+
+        // A bigger jump keeps its direction but is scaled down to the max
+        entity.set_velocity_y(-8.0);
+        let velocity = entity.get_velocity();
+        assert!((velocity.magnitude() - 5.0).abs() < 1e-9);
+        assert!((velocity.angle() - f64::atan2(-8.0, 4.0)).abs() < 1e-9);
+
+        entity.set_velocity_x(0.0);
+        assert_eq!(entity.get_velocity(), [0.0, velocity.y].into());
+
+        entity.set_velocity([3.0, 0.0].into());
+        entity.add_velocity([1.0, 0.0].into());
+        assert_eq!(entity.get_velocity(), [4.0, 0.0].into());
+        entity.add_velocity([3.0, 0.0].into());
+        assert_eq!(entity.get_velocity(), [5.0, 0.0].into());
+    }
 }
@@ -2,12 +2,13 @@ use graphics::Context;
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::{Entity, HasEntity};
 use crate::{
     error::RenderResult,
     rendering::{registry::TextureRegistry, Camera},
-    scene::GameTickArgs,
+    scene::{GameTickArgs, SpawnArgs},
     types::HasUuid,
     ScarabResult,
 };
@@ -36,12 +37,34 @@ where
     fn maybe_player_mut(&mut self) -> Option<&mut Self::Player>;
 
     /// Runs the game tick update for the entity. By default runs the gametick on the inner entity
-    fn game_tick(&mut self, _this_idx: usize, args: &mut GameTickArgs<Self>) -> ScarabResult<()> {
+    /// `this` is the entity's own handle, i.e. to use as the source of its effects
+    fn game_tick(
+        &mut self,
+        _this: EntityHandle,
+        args: &mut GameTickArgs<Self>,
+    ) -> ScarabResult<()> {
         self.inner_entity_mut()
             .game_tick(args)
+            .map(|_| ())
             .map_err(|e| e.into())
     }
 
+    /// Runs once the entity has been registered to a scene. By default does nothing
+    fn on_spawn(&mut self, _args: &SpawnArgs) -> ScarabResult<()> {
+        Ok(())
+    }
+
+    /// Runs once the entity has been removed from a scene. By default does nothing
+    fn on_despawn(&mut self, _args: &SpawnArgs) -> ScarabResult<()> {
+        Ok(())
+    }
+
+    /// The layer the entity is drawn on. Entities on higher layers are drawn over entities on lower layers,
+    /// and entities on the same layer are drawn in the order they were registered. By default 0
+    fn get_layer(&self) -> i32 {
+        0
+    }
+
     /// Controls how the registered object renders the inner entity.
     /// This should usually be done by pairing the registered entity with something that impls [crate::rendering::View]
     fn render(
@@ -72,84 +95,274 @@ pub trait RegisteredDebugEntity: RegisteredEntity {
     ) -> RenderResult<()>;
 }
 
-/// The registry of all entities that are active in a scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Refers to an entity that was registered to an [EntityRegistry]
+pub struct EntityHandle {
+    /// The entity's index in the registry, which doesn't change while it's registered
+    pub index: usize,
+    /// How many entities had been removed from the index before this one was registered to it,
+    /// so that handles to removed entities don't refer to whatever reuses their index
+    #[serde(default)]
+    pub generation: u32,
+    /// The entity's unique identifier
+    pub uuid: Uuid,
+}
+
+/// The registry of all entities that are active in a scene.
+///
+/// Each entity keeps the same index for as long as it's registered.
+/// Removing an entity leaves its index empty until a later registration reuses it
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EntityRegistry<E> {
-    inner: Vec<E>,
+    slots: Vec<Option<E>>,
+    /// The generation of each slot, which goes up each time its entity is removed
+    generations: Vec<u32>,
+    /// Empty slots, in the order they'll be reused
+    free: Vec<usize>,
 }
 
 impl<E> Default for EntityRegistry<E> {
     fn default() -> Self {
-        Self { inner: Vec::new() }
+        Self {
+            slots: Vec::new(),
+            generations: Vec::new(),
+            free: Vec::new(),
+        }
     }
 }
 
 impl<E: RegisteredEntity> EntityRegistry<E> {
-    /// Attempts to register a new entity to the scene
-    pub fn register(&mut self, to_register: E) -> ScarabResult<()> {
-        self.inner.push(to_register);
-        Ok(())
+    /// Attempts to register a new entity to the scene, reusing the index of a removed entity if there is one.
+    /// Returns a handle to get the entity from the registry later
+    pub fn register(&mut self, to_register: E) -> ScarabResult<EntityHandle> {
+        let uuid = to_register.uuid();
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = Some(to_register);
+                index
+            }
+            None => {
+                self.slots.push(Some(to_register));
+                self.generations.push(0);
+                self.slots.len() - 1
+            }
+        };
+
+        Ok(EntityHandle {
+            index,
+            generation: self.generations[index],
+            uuid,
+        })
+    }
+
+    /// Gets a reference to the entity the handle refers to if it's still registered
+    pub fn get(&self, handle: EntityHandle) -> Option<&E> {
+        if self.generations.get(handle.index) != Some(&handle.generation) {
+            return None;
+        }
+        self.get_one(handle.index)
+            .filter(|e| e.uuid() == handle.uuid)
+    }
+
+    /// Gets a mutable reference to the entity the handle refers to if it's still registered
+    pub fn get_mut(&mut self, handle: EntityHandle) -> Option<&mut E> {
+        if self.generations.get(handle.index) != Some(&handle.generation) {
+            return None;
+        }
+        self.get_one_mut(handle.index)
+            .filter(|e| e.uuid() == handle.uuid)
+    }
+
+    /// The handle of the entity at the given index if there is one
+    pub fn handle_at(&self, i: usize) -> Option<EntityHandle> {
+        self.get_one(i).map(|e| EntityHandle {
+            index: i,
+            generation: self.generations[i],
+            uuid: e.uuid(),
+        })
+    }
+
+    /// Removes the entity with the given uuid from the registry if it's registered.
+    /// Other entities keep their indices, and handles to the removed entity stop resolving
+    pub fn remove(&mut self, uuid: Uuid) -> Option<E> {
+        let index = self.index_of_uuid(uuid)?;
+        let removed = self.slots[index].take();
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(index);
+        removed
     }
 
     /// Gets a reference to the registered player
     pub fn player(&self) -> Option<&E::Player> {
-        self.inner.iter().find_map(E::maybe_player)
+        self.iter().find_map(E::maybe_player)
     }
 
     /// Gets a mutable reference to the registered player
     pub fn player_mut(&mut self) -> Option<&mut E::Player> {
-        self.inner.iter_mut().find_map(E::maybe_player_mut)
+        self.iter_mut().find_map(E::maybe_player_mut)
     }
 
     /// The number of currently registered entities
     pub fn len(&self) -> usize {
-        self.inner.len()
+        self.slots.len() - self.free.len()
+    }
+
+    /// One more than the highest index an entity can currently have.
+    /// Indices below this may be empty, see [EntityRegistry::get_one]
+    pub fn index_bound(&self) -> usize {
+        self.slots.len()
     }
 
     /// Gets a reference to the registered entity at the given index if it exists
     pub fn get_one(&self, i: usize) -> Option<&E> {
-        self.inner.get(i)
+        self.slots.get(i).and_then(Option::as_ref)
     }
 
     /// Gets a mutable reference to the registered entity at the given index if it exists
     pub fn get_one_mut(&mut self, i: usize) -> Option<&mut E> {
-        self.inner.get_mut(i)
+        self.slots.get_mut(i).and_then(Option::as_mut)
+    }
+
+    /// The current index of the registered entity with the given uuid
+    pub fn index_of_uuid(&self, uuid: Uuid) -> Option<usize> {
+        self.iter_indexed()
+            .find(|(_, e)| e.uuid() == uuid)
+            .map(|(i, _)| i)
+    }
+
+    /// Iterates across the registered entities along with their indices
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &E)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| e.as_ref().map(|e| (i, e)))
+    }
+
+    /// Iterates across mutable references to the registered entities along with their indices
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = (usize, &mut E)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, e)| e.as_mut().map(|e| (i, e)))
+    }
+
+    /// Iterates across mutable references to the registered entities along with their handles
+    pub fn iter_handles_mut(&mut self) -> impl Iterator<Item = (EntityHandle, &mut E)> {
+        self.slots
+            .iter_mut()
+            .zip(self.generations.iter())
+            .enumerate()
+            .filter_map(|(index, (e, &generation))| {
+                e.as_mut().map(|e| {
+                    let handle = EntityHandle {
+                        index,
+                        generation,
+                        uuid: e.uuid(),
+                    };
+                    (handle, e)
+                })
+            })
     }
 
     /// Iterates across the registered entities
-    pub fn iter(&self) -> core::slice::Iter<'_, E> {
-        self.inner.iter()
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.slots.iter().flatten()
     }
 
     /// Iterates across mutable references to the registered entities
-    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, E> {
-        self.inner.iter_mut()
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut E> {
+        self.slots.iter_mut().flatten()
     }
 }
 
 impl<E> IntoIterator for EntityRegistry<E> {
     type Item = E;
-    type IntoIter = std::vec::IntoIter<E>;
+    type IntoIter = core::iter::Flatten<std::vec::IntoIter<Option<E>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.into_iter()
+        self.slots.into_iter().flatten()
     }
 }
 
 impl<'a, E> IntoIterator for &'a EntityRegistry<E> {
     type Item = &'a E;
-    type IntoIter = core::slice::Iter<'a, E>;
+    type IntoIter = core::iter::Flatten<core::slice::Iter<'a, Option<E>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.iter()
+        self.slots.iter().flatten()
     }
 }
 
 impl<'a, E> IntoIterator for &'a mut EntityRegistry<E> {
     type Item = &'a mut E;
-    type IntoIter = core::slice::IterMut<'a, E>;
+    type IntoIter = core::iter::Flatten<core::slice::IterMut<'a, Option<E>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.iter_mut()
+        self.slots.iter_mut().flatten()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TestEntity;
+
+    #[test]
+    fn index_of_uuid_finds_registered_entities() {
+        let mut registry = EntityRegistry::default();
+        let handles: Vec<EntityHandle> = (0..3)
+            .map(|i| {
+                registry
+                    .register(TestEntity::at([i as f64 * 10.0, 0.0, 5.0, 5.0]))
+                    .unwrap()
+            })
+            .collect();
+
+        for handle in &handles {
+            assert_eq!(registry.index_of_uuid(handle.uuid), Some(handle.index));
+        }
+        assert_eq!(registry.index_of_uuid(Uuid::new_v4()), None);
+
+        let indexed: Vec<(usize, Uuid)> = registry
+            .iter_indexed()
+            .map(|(i, e)| (i, e.uuid()))
+            .collect();
+        let expected: Vec<(usize, Uuid)> = handles.iter().map(|h| (h.index, h.uuid)).collect();
+        assert_eq!(indexed, expected);
+
+        registry.remove(handles[0].uuid).unwrap();
+        assert_eq!(registry.index_of_uuid(handles[0].uuid), None);
+        assert_eq!(registry.index_of_uuid(handles[2].uuid), Some(2));
+    }
+
+    #[test]
+    fn stale_handles_stop_resolving() {
+        let mut registry = EntityRegistry::default();
+        let first = registry
+            .register(TestEntity::at([0.0, 0.0, 5.0, 5.0]))
+            .unwrap();
+        let second = registry
+            .register(TestEntity::at([10.0, 0.0, 5.0, 5.0]))
+            .unwrap();
+
+        assert!(registry.remove(first.uuid).is_some());
+        assert!(registry.get(first).is_none());
+        assert!(registry.remove(first.uuid).is_none());
+        // Other entities keep their indices
+        assert_eq!(registry.get(second).unwrap().uuid(), second.uuid);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.index_bound(), 2);
+
+        // The next entity reuses the removed index without reviving the old handle
+        let third = registry
+            .register(TestEntity::at([20.0, 0.0, 5.0, 5.0]))
+            .unwrap();
+        assert_eq!(third.index, first.index);
+        assert_ne!(third.generation, first.generation);
+        assert!(registry.get(first).is_none());
+        assert_eq!(registry.get(third).unwrap().uuid(), third.uuid);
+        assert_eq!(registry.handle_at(third.index), Some(third));
+        assert_eq!(registry.get(second).unwrap().uuid(), second.uuid);
+        assert_eq!(registry.len(), 2);
     }
 }
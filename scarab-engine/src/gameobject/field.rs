@@ -1,12 +1,20 @@
 use core::slice::Iter;
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
-use graphics::{types::Color, Context};
+use graphics::{
+    types::{Color, Scalar},
+    Context,
+};
 use opengl_graphics::GlGraphics;
-use petgraph::{graph::NodeIndex, prelude::DiGraph, stable_graph::DefaultIx, visit::EdgeRef};
+use petgraph::{
+    algo::astar,
+    graph::NodeIndex,
+    stable_graph::{DefaultIx, StableDiGraph},
+    visit::{EdgeFiltered, EdgeRef},
+};
 use piston::RenderArgs;
-use serde::{Deserialize, Serialize};
-use shapes::Point;
+use serde::{Deserialize, Serialize, Serializer};
+use shapes::{Point, Size};
 
 use super::{HasSolidity, Solidity, NO_SOLIDITY, SOLID};
 use crate::{
@@ -14,15 +22,19 @@ use crate::{
     rendering::{registry::TextureRegistry, Camera, View},
     types::{
         physbox::{HasBox, HasBoxMut, PhysBox},
-        BoxEdge,
+        Axis, BoxEdge,
     },
     PhysicsError, PhysicsResult,
 };
 
 /// A graph of `Cell`s on the field, with the edges between them being the
 /// physical side of the cell where the edge appears, and whether or not
-/// that edge is passable by solidity entering/exiting rules
-pub type FieldGraphInner = DiGraph<Cell, (BoxEdge, bool)>;
+/// that edge is passable by solidity entering/exiting rules.
+/// Removing a cell doesn't change the indices of any other cells
+pub type FieldGraphInner = StableDiGraph<Cell, (BoxEdge, bool)>;
+
+/// How far around the point where a raycast leaves a cell to look for the next cell
+const RAYCAST_EPSILON: Scalar = 1e-6;
 
 /// A field is a graph of rectangles ([cells](Cell)) that aids in movement within a scene
 ///
@@ -32,9 +44,198 @@ pub type FieldGraphInner = DiGraph<Cell, (BoxEdge, bool)>;
 /// used to construct a graph for determining if inter-cell movement is possible
 ///
 /// For simplicity, it's assumed that intra-cell movement is always possible
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// A field only holds collision geometry, how it's rendered is up to a separate
+/// [View] (i.e. [FieldColorView]). So headless simulations (like a server) can
+/// use and serialize a field without any of the rendering data
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "FieldData")]
 pub struct Field {
     graph: FieldGraphInner,
+    /// Narrows down which cells could be at a position
+    index: CellIndex,
+}
+
+#[derive(Serialize, Deserialize)]
+/// The serialized form of a [Field]. Only the cells are saved, the graph's edges and the index
+/// are rebuilt from them when it's loaded so that saves stay small and don't depend on how edges are built.
+/// Any gaps left by [removed cells](Field::remove_cell) are closed, so cell indices may change
+struct FieldData<C = Cell> {
+    cells: Vec<C>,
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FieldData {
+            cells: self.graph.node_weights().collect::<Vec<&Cell>>(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl TryFrom<FieldData> for Field {
+    type Error = PhysicsError;
+
+    fn try_from(data: FieldData) -> PhysicsResult<Self> {
+        Field::new(data.cells)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A uniform grid of buckets over the field, each holding the cells that overlap it
+struct CellIndex {
+    /// The width and height of each bucket
+    bucket_size: Scalar,
+    buckets: HashMap<(i64, i64), Vec<NodeIndex>>,
+    /// Cells covering more than [MAX_CELL_BUCKETS] buckets, kept out of the buckets and in index order
+    oversized: Vec<NodeIndex>,
+    /// The summed [size](CellIndex::cell_size) of the indexed cells, to tell when the bucket size stops fitting them
+    total_size: Scalar,
+    count: usize,
+}
+
+/// How far the average cell size can drift from the bucket size before the index is rebuilt
+const INDEX_REBUILD_FACTOR: Scalar = 4.0;
+
+/// The most buckets a single cell is put in. Bigger cells go in the index's oversized list instead,
+/// so one huge cell among many small ones doesn't fill the map with buckets
+const MAX_CELL_BUCKETS: i64 = 64;
+
+impl CellIndex {
+    fn new(graph: &FieldGraphInner) -> Self {
+        // Sizing the buckets around the average cell keeps both the number of buckets
+        // per cell and the number of cells per bucket small
+        let total_size: Scalar = graph
+            .node_weights()
+            .map(|c| CellIndex::cell_size(&c.physbox))
+            .sum();
+        let count = graph.node_count();
+        let bucket_size = if count == 0 {
+            1.0
+        } else {
+            total_size / count as Scalar
+        };
+
+        let mut index = Self {
+            bucket_size,
+            buckets: HashMap::new(),
+            oversized: Vec::new(),
+            total_size: 0.0,
+            count: 0,
+        };
+        for i in graph.node_indices() {
+            index.insert(i, &graph[i].physbox);
+        }
+
+        index
+    }
+
+    /// The keys of every bucket the box overlaps
+    fn buckets_of(&self, physbox: &PhysBox) -> impl Iterator<Item = (i64, i64)> {
+        let (left, top) = self.bucket_of(*physbox.pos());
+        let (right, bottom) = self.bucket_of([physbox.right_x(), physbox.bottom_y()].into());
+        (left..=right).flat_map(move |x| (top..=bottom).map(move |y| (x, y)))
+    }
+
+    /// Whether the box covers too many buckets to be put in them
+    fn is_oversized(&self, physbox: &PhysBox) -> bool {
+        let (left, top) = self.bucket_of(*physbox.pos());
+        let (right, bottom) = self.bucket_of([physbox.right_x(), physbox.bottom_y()].into());
+        (right - left + 1).saturating_mul(bottom - top + 1) > MAX_CELL_BUCKETS
+    }
+
+    /// The average of a cell's width and height
+    fn cell_size(physbox: &PhysBox) -> Scalar {
+        (physbox.size().w + physbox.size().h) / 2.0
+    }
+
+    /// Whether the bucket size still fits the cells if a cell with the given box were added,
+    /// or if no box is given, the cells as they are
+    fn fits_with(&self, added: Option<&PhysBox>) -> bool {
+        let (total_size, count) = match added {
+            Some(physbox) => (
+                self.total_size + CellIndex::cell_size(physbox),
+                self.count + 1,
+            ),
+            None => (self.total_size, self.count),
+        };
+        if count == 0 {
+            return true;
+        }
+        let ratio = total_size / count as Scalar / self.bucket_size;
+        (1.0 / INDEX_REBUILD_FACTOR..=INDEX_REBUILD_FACTOR).contains(&ratio)
+    }
+
+    fn insert(&mut self, i: NodeIndex, physbox: &PhysBox) {
+        self.total_size += CellIndex::cell_size(physbox);
+        self.count += 1;
+        if self.is_oversized(physbox) {
+            if let Err(pos) = self.oversized.binary_search(&i) {
+                self.oversized.insert(pos, i);
+            }
+            return;
+        }
+        for key in self.buckets_of(physbox) {
+            // Keep each bucket sorted like the graph's nodes
+            let bucket = self.buckets.entry(key).or_default();
+            if let Err(pos) = bucket.binary_search(&i) {
+                bucket.insert(pos, i);
+            }
+        }
+    }
+
+    fn remove(&mut self, i: NodeIndex, physbox: &PhysBox) {
+        self.total_size -= CellIndex::cell_size(physbox);
+        self.count -= 1;
+        if self.is_oversized(physbox) {
+            self.oversized.retain(|other| *other != i);
+            return;
+        }
+        for key in self.buckets_of(physbox) {
+            if let Some(bucket) = self.buckets.get_mut(&key) {
+                bucket.retain(|other| *other != i);
+            }
+        }
+    }
+
+    fn bucket_of(&self, pos: Point) -> (i64, i64) {
+        (
+            (pos.x / self.bucket_size).floor() as i64,
+            (pos.y / self.bucket_size).floor() as i64,
+        )
+    }
+
+    /// The cells that might overlap the given box, in index order
+    fn candidates_for_box(&self, physbox: &PhysBox) -> Vec<NodeIndex> {
+        let mut candidates: Vec<NodeIndex> = self
+            .buckets_of(physbox)
+            .filter_map(|key| self.buckets.get(&key))
+            .flatten()
+            .chain(self.oversized.iter())
+            .copied()
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// The cells that might contain the given position, in index order
+    fn candidates(&self, pos: Point) -> Vec<NodeIndex> {
+        let bucket = self
+            .buckets
+            .get(&self.bucket_of(pos))
+            .map_or(&[][..], |b| b.as_slice());
+        if self.oversized.is_empty() {
+            return bucket.to_vec();
+        }
+        let mut candidates: Vec<NodeIndex> = bucket
+            .iter()
+            .chain(self.oversized.iter())
+            .copied()
+            .collect();
+        candidates.sort();
+        candidates
+    }
 }
 
 impl Field {
@@ -47,20 +248,170 @@ impl Field {
             graph.node_weight_mut(i).map(|c| c.i = i);
         }
 
-        Field::build_cells(&mut graph)?;
+        let index = CellIndex::new(&graph);
+        Field::build_cells(&mut graph, &index)?;
 
         // TODO: potential validation steps:
-        // - ensure that cells dont overlap (probably should be done before the edges are built)
-        //     it most likely can't be done exhaustively < O(n^2)
-        //     but I don't know if that's the end of the world since this is only done
-        //     on level loading
+        // - ensure that cells dont overlap: opt-in with `Field::new_validated`
         // - ensure there are no gaps in between cells
         //     this would probably take a very long time unless I can come up with a clever alg
 
-        Ok(Self { graph })
+        Ok(Self { graph, index })
+    }
+
+    /// Like [Field::new], but first checks that none of the cells overlap
+    pub fn new_validated(cells: Vec<Cell>) -> PhysicsResult<Self> {
+        // Sweep across the cells from left to right, only comparing each cell
+        // to the cells whose x-range it's within
+        let mut order: Vec<usize> = (0..cells.len()).collect();
+        order.sort_by(|a, b| {
+            cells[*a]
+                .physbox
+                .left_x()
+                .total_cmp(&cells[*b].physbox.left_x())
+        });
+
+        let mut active: Vec<usize> = Vec::new();
+        for i in order {
+            let physbox = &cells[i].physbox;
+            active.retain(|j| cells[*j].physbox.right_x() > physbox.left_x());
+            if let Some(j) = active
+                .iter()
+                .find(|j| cells[**j].physbox.has_overlap(physbox))
+            {
+                return Err(PhysicsError::OverlappingCells {
+                    a: usize::min(i, *j),
+                    b: usize::max(i, *j),
+                });
+            }
+            active.push(i);
+        }
+
+        Field::new(cells)
+    }
+
+    /// Builds a field from a tilemap, with one `tile_size` cell per tile. The tile at
+    /// `tiles[row][col]` is positioned at `(col * tile_size.w, row * tile_size.h)` and
+    /// its solidity is looked up by its id in `solidity_map`
+    pub fn from_grid(
+        tiles: &[&[u8]],
+        tile_size: Size,
+        solidity_map: &HashMap<u8, Solidity>,
+    ) -> PhysicsResult<Self> {
+        let solidities = Field::grid_solidities(tiles, solidity_map)?;
+        let mut cells = Vec::new();
+        for (row, row_solidities) in solidities.iter().enumerate() {
+            for (col, solidity) in row_solidities.iter().enumerate() {
+                cells.push(Field::grid_cell(*solidity, tile_size, row, col, 1, 1)?);
+            }
+        }
+
+        Field::new(cells)
+    }
+
+    /// Like [Field::from_grid], but greedily merges rectangles of neighboring tiles with the
+    /// same solidity into a single cell. Entities collide with the merged field exactly
+    /// like the unmerged one, but it has far fewer cells to search through
+    pub fn from_grid_merged(
+        tiles: &[&[u8]],
+        tile_size: Size,
+        solidity_map: &HashMap<u8, Solidity>,
+    ) -> PhysicsResult<Self> {
+        let solidities = Field::grid_solidities(tiles, solidity_map)?;
+        let mut merged: Vec<Vec<bool>> = solidities.iter().map(|r| vec![false; r.len()]).collect();
+        let mut cells = Vec::new();
+
+        for row in 0..solidities.len() {
+            for col in 0..solidities[row].len() {
+                if merged[row][col] {
+                    continue;
+                }
+                let solidity = solidities[row][col];
+                let fits = |r: usize, c: usize, merged: &Vec<Vec<bool>>| {
+                    solidities[r].get(c) == Some(&solidity) && !merged[r][c]
+                };
+
+                // Grow right as far as possible, then down for as long as every tile
+                // under the whole width matches
+                let mut width = 1;
+                while fits(row, col + width, &merged) {
+                    width += 1;
+                }
+                let mut height = 1;
+                while row + height < solidities.len()
+                    && (col..col + width).all(|c| fits(row + height, c, &merged))
+                {
+                    height += 1;
+                }
+
+                for merged_row in &mut merged[row..row + height] {
+                    merged_row[col..col + width].fill(true);
+                }
+                cells.push(Field::grid_cell(
+                    solidity, tile_size, row, col, width, height,
+                )?);
+            }
+        }
+
+        Field::new(cells)
+    }
+
+    /// Looks up the solidity of every tile in a tilemap
+    fn grid_solidities(
+        tiles: &[&[u8]],
+        solidity_map: &HashMap<u8, Solidity>,
+    ) -> PhysicsResult<Vec<Vec<Solidity>>> {
+        tiles
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|tile| {
+                        solidity_map
+                            .get(tile)
+                            .copied()
+                            .ok_or(PhysicsError::UnknownTile(*tile))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A cell covering `width` by `height` tiles with its upper left tile at the given row and column
+    fn grid_cell(
+        solidity: Solidity,
+        tile_size: Size,
+        row: usize,
+        col: usize,
+        width: usize,
+        height: usize,
+    ) -> PhysicsResult<Cell> {
+        Ok(Cell::new(
+            solidity,
+            PhysBox::new([
+                col as Scalar * tile_size.w,
+                row as Scalar * tile_size.h,
+                width as Scalar * tile_size.w,
+                height as Scalar * tile_size.h,
+            ])?,
+        ))
+    }
+
+    fn build_cells(graph: &mut FieldGraphInner, index: &CellIndex) -> PhysicsResult<()> {
+        // Initialize the neighbors and edges
+        let indices: Vec<NodeIndex> = graph.node_indices().collect();
+        for cell_idx in indices {
+            Field::build_cell_edges(graph, index, cell_idx)?;
+        }
+
+        Ok(())
     }
 
-    fn build_cells(graph: &mut FieldGraphInner) -> PhysicsResult<()> {
+    /// Adds the graph edges from the given cell to each of the cells bordering it
+    fn build_cell_edges(
+        graph: &mut FieldGraphInner,
+        index: &CellIndex,
+        cell_idx: NodeIndex,
+    ) -> PhysicsResult<()> {
         // Find the bordering cells along the given edge,
         // and mark the appropriate graph edges
         // c: the current cell
@@ -74,6 +425,7 @@ impl Field {
         fn cell_edges(
             this_cell_idx: NodeIndex,
             graph: &mut FieldGraphInner,
+            index: &CellIndex,
             test_pos: Point,
             edge: BoxEdge,
         ) -> PhysicsResult<()> {
@@ -91,7 +443,7 @@ impl Field {
                 // or exited for each cell)
                 // Then set the new test pos to the far end of the neighbor
                 let new_normal_component = if let Some(cell_at_test_pos) =
-                    Field::cell_at_pos_internal(graph.node_weights(), test_pos)
+                    Field::cell_at_pos_indexed(graph, index, test_pos)
                 {
                     let new_normal_component =
                         cell_at_test_pos.physbox.get_far_axis(edge.parallel_axis());
@@ -128,31 +480,88 @@ impl Field {
             Ok(())
         }
 
-        // Initialize the neighbors and edges
-        for cell_idx in graph.node_indices() {
-            let physbox = Field::cell_at_idx(graph, cell_idx)?.physbox;
-            let mut test_pos;
+        let physbox = Field::cell_at_idx(graph, cell_idx)?.physbox;
+        let mut test_pos;
 
-            // Along the top edge
-            test_pos = *physbox.pos() - [0.0, 1.0];
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Top)?;
+        // Along the top edge
+        test_pos = *physbox.pos() - [0.0, 1.0];
+        cell_edges(cell_idx, graph, index, test_pos, BoxEdge::Top)?;
 
-            // Along the left edge
-            test_pos = *physbox.pos() - [1.0, 0.0];
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Left)?;
+        // Along the left edge
+        test_pos = *physbox.pos() - [1.0, 0.0];
+        cell_edges(cell_idx, graph, index, test_pos, BoxEdge::Left)?;
 
-            // Along the bottom edge
-            test_pos = [physbox.left_x(), physbox.bottom_y()].into();
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Bottom)?;
+        // Along the bottom edge
+        test_pos = [physbox.left_x(), physbox.bottom_y()].into();
+        cell_edges(cell_idx, graph, index, test_pos, BoxEdge::Bottom)?;
 
-            // Along the right edge
-            test_pos = [physbox.right_x(), physbox.top_y()].into();
-            cell_edges(cell_idx, graph, test_pos, BoxEdge::Right)?;
-        }
+        // Along the right edge
+        test_pos = [physbox.right_x(), physbox.top_y()].into();
+        cell_edges(cell_idx, graph, index, test_pos, BoxEdge::Right)?;
 
         Ok(())
     }
 
+    /// Replaces all of the graph edges from the given cell, for when its surroundings changed
+    fn rebuild_cell_edges(
+        graph: &mut FieldGraphInner,
+        index: &CellIndex,
+        cell_idx: NodeIndex,
+    ) -> PhysicsResult<()> {
+        let old_edges: Vec<_> = graph.edges(cell_idx).map(|e| e.id()).collect();
+        for edge in old_edges {
+            graph.remove_edge(edge);
+        }
+        Field::build_cell_edges(graph, index, cell_idx)
+    }
+
+    /// Adds a cell to the field and connects it to the cells bordering it.
+    /// Only the new cell's and its neighbors' graph edges are rebuilt.
+    /// The indices of the other cells stay the same
+    pub fn add_cell(&mut self, cell: Cell) -> PhysicsResult<NodeIndex> {
+        let i = self.graph.add_node(cell);
+        self.graph[i].i = i;
+        // Rebuild rather than insert when the cell would leave the buckets sized badly,
+        // i.e. a large cell added to a field that started empty
+        if self.index.fits_with(Some(&self.graph[i].physbox)) {
+            self.index.insert(i, &self.graph[i].physbox);
+        } else {
+            self.index = CellIndex::new(&self.graph);
+        }
+
+        Field::build_cell_edges(&mut self.graph, &self.index, i)?;
+        let neighbors: Vec<NodeIndex> = self.graph.neighbors(i).collect();
+        for neighbor in neighbors {
+            Field::rebuild_cell_edges(&mut self.graph, &self.index, neighbor)?;
+        }
+
+        Ok(i)
+    }
+
+    /// Removes the cell with the given index from the field, returning it.
+    /// Only the removed cell's neighbors have their graph edges rebuilt.
+    /// The indices of the other cells stay the same
+    pub fn remove_cell(&mut self, idx: NodeIndex) -> PhysicsResult<Cell> {
+        let mut neighbors: Vec<NodeIndex> = self.graph.neighbors_undirected(idx).collect();
+        neighbors.sort();
+        neighbors.dedup();
+
+        let cell = self
+            .graph
+            .remove_node(idx)
+            .ok_or_else(|| PhysicsError::FieldIndex(idx.index()))?;
+        self.index.remove(idx, &cell.physbox);
+        if !self.index.fits_with(None) {
+            self.index = CellIndex::new(&self.graph);
+        }
+
+        for neighbor in neighbors {
+            Field::rebuild_cell_edges(&mut self.graph, &self.index, neighbor)?;
+        }
+
+        Ok(cell)
+    }
+
     fn cell_at_idx(graph: &FieldGraphInner, idx: NodeIndex) -> PhysicsResult<&Cell> {
         graph
             .node_weight(idx)
@@ -163,9 +572,6 @@ impl Field {
         cells: I,
         pos: Point,
     ) -> Option<&'a Cell> {
-        // The real challenge of this will be to try and do it in less than O(n)
-        // I could see some sort of spanning tree setup to do this more quickly
-        // in as little as O(log(n))
         for c in cells {
             if c.physbox.contains_pos(pos) {
                 return Some(c);
@@ -174,9 +580,32 @@ impl Field {
         None
     }
 
+    /// Looks the cell at the given point up through the index rather than checking every cell
+    fn cell_at_pos_indexed<'a>(
+        graph: &'a FieldGraphInner,
+        index: &CellIndex,
+        pos: Point,
+    ) -> Option<&'a Cell> {
+        let candidates = index
+            .candidates(pos)
+            .into_iter()
+            .filter_map(|i| graph.node_weight(i));
+        Field::cell_at_pos_internal(candidates, pos)
+    }
+
     /// Returns the cell at the given point on the field if any exist
     pub fn cell_at_pos(&self, pos: Point) -> Option<&Cell> {
-        Field::cell_at_pos_internal(self.graph.node_weights(), pos)
+        Field::cell_at_pos_indexed(&self.graph, &self.index, pos)
+    }
+
+    /// Returns every cell that the physbox overlaps, in index order
+    pub fn cells_overlapping_box(&self, physbox: &PhysBox) -> Vec<&Cell> {
+        self.index
+            .candidates_for_box(physbox)
+            .into_iter()
+            .filter_map(|i| self.graph.node_weight(i))
+            .filter(|c| c.physbox.has_overlap(physbox))
+            .collect()
     }
 
     /// Given a cell on the field and a physbox, returns the neighbors of
@@ -197,6 +626,150 @@ impl Field {
 
         Ok(neighbors)
     }
+
+    /// Walks the cells along the segment from `from` to `to`, returning the first [SOLID] cell
+    /// it hits along with the point where it enters that cell.
+    /// Returns `None` if the segment reaches `to` unobstructed or leaves the field before hitting anything
+    pub fn raycast(&self, from: Point, to: Point) -> Option<(NodeIndex, Point)> {
+        let point_at = |t: Scalar| -> Point {
+            [from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t].into()
+        };
+
+        let mut current = self.cell_at_pos(from)?;
+        let mut t = 0.0;
+        loop {
+            if current.solidity == SOLID {
+                return Some((current.i, point_at(t)));
+            }
+
+            let t_exit = Field::segment_exit(&current.physbox, from, to);
+            if t_exit >= 1.0 {
+                return None;
+            }
+
+            // The next cell is whichever one the segment enters first after leaving this one.
+            // Searching around the exit point (rather than just the graph neighbors) also
+            // finds cells that the segment enters diagonally through a corner
+            let exit = point_at(t_exit);
+            let nearby = PhysBox::new([
+                exit.x - RAYCAST_EPSILON,
+                exit.y - RAYCAST_EPSILON,
+                2.0 * RAYCAST_EPSILON,
+                2.0 * RAYCAST_EPSILON,
+            ])
+            .ok()?;
+            let (entry, next) = self
+                .index
+                .candidates_for_box(&nearby)
+                .into_iter()
+                .filter(|i| *i != current.i)
+                .filter_map(|i| self.graph.node_weight(i))
+                .filter(|c| Field::segment_exit(&c.physbox, from, to) > t_exit)
+                .filter_map(|c| c.physbox.segment_entry(from, to).map(|entry| (entry, c)))
+                .min_by(|a, b| a.0.total_cmp(&b.0))?;
+
+            t = f64::max(entry, t_exit);
+            current = next;
+        }
+    }
+
+    /// How far along the segment from `from` to `to` it leaves the given box, as a fraction of the segment.
+    /// Assumes the segment passes through the box
+    fn segment_exit(physbox: &PhysBox, from: Point, to: Point) -> Scalar {
+        [Axis::X, Axis::Y]
+            .iter()
+            .filter_map(|axis| {
+                let start = axis.component_of_point(&from);
+                let delta = axis.component_of_point(&to) - start;
+                let bound = if delta > 0.0 {
+                    physbox.get_far_axis(*axis)
+                } else if delta < 0.0 {
+                    physbox.get_near_axis(*axis)
+                } else {
+                    return None;
+                };
+                Some((bound - start) / delta)
+            })
+            .fold(Scalar::INFINITY, Scalar::min)
+    }
+
+    /// Finds the shortest path of cells from the cell at `from` to the cell at `to`, only moving
+    /// between cells over passable edges. The path includes both the start and end cells.
+    /// Returns `None` if either point is off the field or there is no such path
+    pub fn find_path(&self, from: Point, to: Point) -> Option<Vec<NodeIndex>> {
+        let start = self.cell_at_pos(from)?.i;
+        let goal = self.cell_at_pos(to)?.i;
+        let goal_center = self.graph[goal].physbox.center();
+        let distance = |a: Point, b: Point| f64::hypot(a.x - b.x, a.y - b.y);
+
+        let passable = EdgeFiltered::from_fn(&self.graph, |e| e.weight().1);
+        astar(
+            &passable,
+            start,
+            |i| i == goal,
+            |e| {
+                distance(
+                    self.graph[e.source()].physbox.center(),
+                    self.graph[e.target()].physbox.center(),
+                )
+            },
+            |i| distance(self.graph[i].physbox.center(), goal_center),
+        )
+        .map(|(_, path)| path)
+    }
+
+    /// Whether the straight line between the two points is not blocked by any
+    /// completely [SOLID] cells. A point inside of a solid cell can't see anything
+    /// Agrees with [Field::raycast], which is what does the walking
+    pub fn has_line_of_sight(&self, from: Point, to: Point) -> bool {
+        let from_cell = self.cell_at_pos(from);
+        let to_cell = self.cell_at_pos(to);
+        if [from_cell, to_cell]
+            .iter()
+            .flatten()
+            .any(|c| c.solidity == SOLID)
+        {
+            return false;
+        }
+
+        // Trivially visible when both points are in the same cell
+        if let (Some(from_cell), Some(to_cell)) = (from_cell, to_cell) {
+            if from_cell.i == to_cell.i {
+                return true;
+            }
+        }
+
+        self.raycast(from, to).is_none()
+    }
+
+    /// An iterator over every cell on the field
+    pub fn cells(&self) -> impl Iterator<Item = &Cell> {
+        self.graph.node_weights()
+    }
+
+    /// Gets the cell with the given index on the field
+    pub fn get_cell(&self, idx: NodeIndex) -> PhysicsResult<&Cell> {
+        Field::cell_at_idx(&self.graph, idx)
+    }
+
+    /// Of the cells with any solidity that the physbox overlaps, finds the one it
+    /// overlaps the most along the given axis.
+    /// Returns that cell's index and the depth of the overlap
+    pub fn deepest_penetration(
+        &self,
+        physbox: &PhysBox,
+        axis: Axis,
+    ) -> Option<(NodeIndex, Scalar)> {
+        self.cells_overlapping_box(physbox)
+            .into_iter()
+            .filter(|c| c.solidity.has_solidity())
+            .map(|c| {
+                let depth = f64::min(physbox.get_far_axis(axis), c.physbox.get_far_axis(axis))
+                    - f64::max(physbox.get_near_axis(axis), c.physbox.get_near_axis(axis));
+                (c.i, depth)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -245,11 +818,16 @@ impl View for FieldColorView {
 /// Represents a static area on a Field that determines the passability for other standard entities
 pub struct Cell {
     /// This cell's index in the Field's `Vec<Cell>`
+    #[serde(skip)]
     i: NodeIndex<DefaultIx>,
     /// Defines how entities can move into/out of this cell
     solidity: Solidity,
     /// The upper left corner and width/height of the cell
     physbox: PhysBox,
+    /// Whether entities that are dropping through platforms can enter this cell
+    /// from the top regardless of its solidity
+    #[serde(default)]
+    drop_through: bool,
 }
 
 impl Cell {
@@ -259,8 +837,26 @@ impl Cell {
             i: NodeIndex::new(0),
             solidity,
             physbox,
+            drop_through: false,
         }
     }
+
+    /// This cell's index in its [Field]
+    pub fn index(&self) -> NodeIndex {
+        self.i
+    }
+
+    /// Marks whether this cell is a platform that entities can drop through.
+    /// See [crate::gameobject::entity::Entity::drop_through]
+    pub fn with_drop_through(mut self, drop_through: bool) -> Self {
+        self.drop_through = drop_through;
+        self
+    }
+
+    /// Whether entities that are dropping through platforms can enter this cell from the top
+    pub fn is_drop_through(&self) -> bool {
+        self.drop_through
+    }
 }
 
 impl HasBox for Cell {
@@ -563,4 +1159,488 @@ mod test {
             &vec![field.cell_at_pos(*boxes[3].pos()).unwrap()]
         );
     }
+
+    #[test]
+    fn deepest_penetration_finds_most_overlapped_solid_cell() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 20.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([10.0, 0.0, 10.0, 22.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([0.0, 20.0, 10.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([10.0, 22.0, 10.0, 8.0]).unwrap()),
+        ])
+        .unwrap();
+        let physbox = PhysBox::new([5.0, 15.0, 10.0, 10.0]).unwrap();
+
+        let (idx, depth) = field.deepest_penetration(&physbox, Axis::Y).unwrap();
+        assert_eq!(
+            field.get_cell(idx).unwrap().physbox,
+            PhysBox::new([0.0, 20.0, 10.0, 10.0]).unwrap()
+        );
+        assert_eq!(depth, 5.0);
+
+        let air_box = PhysBox::new([5.0, 5.0, 10.0, 10.0]).unwrap();
+        assert!(field.deepest_penetration(&air_box, Axis::Y).is_none());
+    }
+
+    #[test]
+    fn has_line_of_sight_is_blocked_by_solid_cells() {
+        let (_, field) = create_test_field();
+
+        // Both in the same open cell
+        assert!(field.has_line_of_sight([15.0, 5.0].into(), [45.0, 5.0].into()));
+        // Across two open cells
+        assert!(field.has_line_of_sight([35.0, 5.0].into(), [40.0, 50.0].into()));
+        // Through the solid cell between two open cells
+        assert!(!field.has_line_of_sight([5.0, 30.0].into(), [40.0, 30.0].into()));
+        // From inside a solid cell
+        assert!(!field.has_line_of_sight([5.0, 5.0].into(), [15.0, 5.0].into()));
+        assert!(!field.has_line_of_sight([5.0, 5.0].into(), [6.0, 6.0].into()));
+    }
+
+    #[test]
+    fn line_of_sight_agrees_with_raycast() {
+        let (_, field) = create_test_field();
+        let points: Vec<Point> = (0..8)
+            .flat_map(|x| (0..8).map(move |y| [x as f64 * 7.0 + 1.5, y as f64 * 7.0 + 2.5].into()))
+            .collect();
+
+        for &from in &points {
+            for &to in &points {
+                let from_open = field.cell_at_pos(from).is_some_and(|c| c.solidity != SOLID);
+                let to_open = field.cell_at_pos(to).is_some_and(|c| c.solidity != SOLID);
+                if from_open && to_open {
+                    assert_eq!(
+                        field.has_line_of_sight(from, to),
+                        field.raycast(from, to).is_none(),
+                        "{from:?} -> {to:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn indexed_cell_at_pos_matches_linear_scan() {
+        // Uneven column widths so cell edges don't line up with the index's buckets
+        let widths = [3.0, 7.0, 1.0, 12.0, 5.5];
+        let mut cells = Vec::new();
+        let mut x = 0.0;
+        for col in 0..100 {
+            let w = widths[col % widths.len()];
+            for row in 0..50 {
+                let solidity = if (col + row) % 3 == 0 {
+                    SOLID
+                } else {
+                    NO_SOLIDITY
+                };
+                cells.push(Cell::new(
+                    solidity,
+                    PhysBox::new([x, row as f64 * 4.0, w, 4.0]).unwrap(),
+                ));
+            }
+            x += w;
+        }
+        let field = Field::new(cells).unwrap();
+
+        let mut pos_x = -5.0;
+        while pos_x < x + 5.0 {
+            let mut pos_y = -5.0;
+            while pos_y < 205.0 {
+                let pos: Point = [pos_x, pos_y].into();
+                let linear = Field::cell_at_pos_internal(field.graph.node_weights(), pos);
+                assert_eq!(
+                    field.cell_at_pos(pos).map(|c| c.i),
+                    linear.map(|c| c.i),
+                    "mismatch at {:?}",
+                    pos
+                );
+                pos_y += 0.5;
+            }
+            pos_x += 0.5;
+        }
+    }
+
+    #[test]
+    fn oversized_cell_stays_out_of_buckets() {
+        // 1000 1x1 tiles with one huge cell under them
+        let mut cells = Vec::new();
+        for row in 0..25 {
+            for col in 0..40 {
+                cells.push(Cell::new(
+                    NO_SOLIDITY,
+                    PhysBox::new([col as f64, row as f64, 1.0, 1.0]).unwrap(),
+                ));
+            }
+        }
+        cells.push(Cell::new(
+            SOLID,
+            PhysBox::new([0.0, 25.0, 10000.0, 10000.0]).unwrap(),
+        ));
+        let field = Field::new(cells).unwrap();
+
+        let huge = NodeIndex::new(1000);
+        assert_eq!(field.index.oversized, vec![huge]);
+        let entries: usize = field.index.buckets.values().map(|b| b.len()).sum();
+        assert!(entries < 4000, "{entries} bucket entries");
+
+        assert_eq!(field.cell_at_pos([5000.0, 5000.0].into()).unwrap().i, huge);
+        assert_eq!(
+            field.cell_at_pos([10.5, 24.5].into()).unwrap().i.index(),
+            970
+        );
+        let overlapping: Vec<NodeIndex> = field
+            .cells_overlapping_box(&PhysBox::new([10.5, 24.5, 1.0, 1.0]).unwrap())
+            .into_iter()
+            .map(|c| c.i)
+            .collect();
+        assert_eq!(
+            overlapping,
+            vec![NodeIndex::new(970), NodeIndex::new(971), huge]
+        );
+
+        // The bottom row of tiles borders the huge cell, found through the index
+        for col in 0..40 {
+            assert!(field.graph.contains_edge(NodeIndex::new(960 + col), huge));
+        }
+    }
+
+    #[test]
+    fn removing_oversized_cell_empties_oversized_list() {
+        let mut field = Field::new(
+            (0..40)
+                .map(|col| {
+                    Cell::new(
+                        NO_SOLIDITY,
+                        PhysBox::new([col as f64, 0.0, 1.0, 1.0]).unwrap(),
+                    )
+                })
+                .collect(),
+        )
+        .unwrap();
+        let huge = field
+            .add_cell(Cell::new(
+                SOLID,
+                PhysBox::new([0.0, 1.0, 40.0, 40.0]).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(field.index.oversized, vec![huge]);
+        assert_eq!(field.cell_at_pos([30.0, 30.0].into()).unwrap().i, huge);
+        assert!(field.graph.contains_edge(NodeIndex::new(0), huge));
+
+        field.remove_cell(huge).unwrap();
+        assert!(field.index.oversized.is_empty());
+        assert!(field.cell_at_pos([30.0, 30.0].into()).is_none());
+    }
+
+    #[test]
+    fn deserialized_field_rebuilds_index() {
+        let (boxes, field) = create_test_field();
+        let json = serde_json::to_string(&field).unwrap();
+        let loaded: Field = serde_json::from_str(&json).unwrap();
+
+        for physbox in &boxes {
+            assert_eq!(
+                loaded.cell_at_pos(*physbox.pos()).map(|c| c.physbox),
+                field.cell_at_pos(*physbox.pos()).map(|c| c.physbox)
+            );
+            assert!(loaded.cell_at_pos(*physbox.pos()).is_some());
+        }
+    }
+
+    #[test]
+    fn field_from_grid() {
+        let tiles: [&[u8]; 3] = [&[1, 1, 1], &[1, 0, 1], &[1, 1, 1]];
+        let solidity_map = HashMap::from([(0, NO_SOLIDITY), (1, SOLID)]);
+        let field = Field::from_grid(&tiles, [10.0, 20.0].into(), &solidity_map).unwrap();
+
+        assert_eq!(field.cells().count(), 9);
+        for (row, row_tiles) in tiles.iter().enumerate() {
+            for (col, tile) in row_tiles.iter().enumerate() {
+                let cell = field
+                    .cell_at_pos([col as f64 * 10.0 + 5.0, row as f64 * 20.0 + 10.0].into())
+                    .unwrap();
+                assert_eq!(
+                    cell.physbox,
+                    PhysBox::new([col as f64 * 10.0, row as f64 * 20.0, 10.0, 20.0]).unwrap()
+                );
+                assert_eq!(cell.solidity, solidity_map[tile]);
+            }
+        }
+
+        let bad_tiles: [&[u8]; 1] = [&[0, 2]];
+        assert_eq!(
+            Field::from_grid(&bad_tiles, [10.0, 20.0].into(), &solidity_map).unwrap_err(),
+            PhysicsError::UnknownTile(2)
+        );
+    }
+
+    #[test]
+    fn merged_grid_collides_like_unmerged_grid() {
+        let tiles: [&[u8]; 5] = [
+            &[1, 1, 1, 1, 1, 1],
+            &[1, 0, 0, 0, 2, 1],
+            &[1, 0, 0, 0, 2, 1],
+            &[1, 0, 1, 0, 0, 1],
+            &[1, 1, 1, 1, 1, 1],
+        ];
+        let solidity_map = HashMap::from([(0, NO_SOLIDITY), (1, SOLID), (2, SOLID)]);
+        let tile_size: Size = [10.0, 10.0].into();
+        let unmerged = Field::from_grid(&tiles, tile_size, &solidity_map).unwrap();
+        let merged = Field::from_grid_merged(&tiles, tile_size, &solidity_map).unwrap();
+        assert!(merged.cells().count() < unmerged.cells().count());
+
+        let mut pos_x = 0.5;
+        while pos_x < 60.0 {
+            let mut pos_y = 0.5;
+            while pos_y < 50.0 {
+                let pos: Point = [pos_x, pos_y].into();
+                assert_eq!(
+                    merged.cell_at_pos(pos).map(|c| c.solidity),
+                    unmerged.cell_at_pos(pos).map(|c| c.solidity),
+                    "mismatch at {:?}",
+                    pos
+                );
+
+                // Every solidity bordering a small box is the same in both fields
+                let physbox = PhysBox::new([pos_x - 0.5, pos_y - 0.5, 1.0, 1.0]).unwrap();
+                let solidities = |field: &Field| {
+                    let mut solidities: Vec<u8> = field
+                        .cells_overlapping_box(&physbox)
+                        .iter()
+                        .map(|c| c.solidity.0)
+                        .collect();
+                    solidities.sort();
+                    solidities.dedup();
+                    solidities
+                };
+                assert_eq!(solidities(&merged), solidities(&unmerged));
+                pos_y += 5.0;
+            }
+            pos_x += 5.0;
+        }
+    }
+
+    #[test]
+    fn raycast_through_open_cells() {
+        let (_, field) = create_test_field();
+        assert_eq!(field.raycast([15.0, 5.0].into(), [45.0, 5.0].into()), None);
+        // From the top strip down through the right column
+        assert_eq!(field.raycast([40.0, 5.0].into(), [40.0, 55.0].into()), None);
+    }
+
+    #[test]
+    fn raycast_hits_solid_wall() {
+        let (_, field) = create_test_field();
+        assert_eq!(
+            field.raycast([20.0, 5.0].into(), [20.0, 30.0].into()),
+            Some((NodeIndex::new(1), [20.0, 10.0].into()))
+        );
+        // Into the wall from the right column, after crossing a cell
+        assert_eq!(
+            field.raycast([45.0, 5.0].into(), [5.0, 45.0].into()),
+            Some((NodeIndex::new(1), [30.0, 20.0].into()))
+        );
+    }
+
+    #[test]
+    fn find_path_goes_around_solid_cells() {
+        let (_, field) = create_test_field();
+        // From the top strip, around the solid cells, and up into the left pocket
+        let path = field.find_path([20.0, 5.0].into(), [5.0, 30.0].into());
+        assert_eq!(
+            path,
+            Some(vec![2, 3, 4, 5].into_iter().map(NodeIndex::new).collect())
+        );
+        assert_eq!(
+            field.find_path([20.0, 5.0].into(), [25.0, 5.0].into()),
+            Some(vec![NodeIndex::new(2)])
+        );
+        assert_eq!(
+            field.find_path([20.0, 5.0].into(), [100.0, 5.0].into()),
+            None
+        );
+    }
+
+    #[test]
+    fn find_path_fails_across_solid_wall() {
+        let tiles: [&[u8]; 3] = [&[0, 1, 0], &[0, 1, 0], &[0, 1, 0]];
+        let solidity_map = HashMap::from([(0, NO_SOLIDITY), (1, SOLID)]);
+        let field = Field::from_grid(&tiles, [10.0, 10.0].into(), &solidity_map).unwrap();
+
+        assert!(field
+            .find_path([5.0, 5.0].into(), [5.0, 25.0].into())
+            .is_some());
+        assert_eq!(field.find_path([5.0, 5.0].into(), [25.0, 5.0].into()), None);
+    }
+
+    #[test]
+    fn field_serializes_as_flat_cell_list() {
+        let (_, field) = create_test_field();
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(!json.contains("edges"));
+
+        let loaded: Field = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.graph.node_count(), field.graph.node_count());
+        for idx in field.graph.node_indices() {
+            assert_eq!(loaded.get_cell(idx).unwrap(), field.get_cell(idx).unwrap());
+            for edge in BoxEdge::iter() {
+                assert_eq!(
+                    neighbor_indices(&loaded, idx, *edge),
+                    neighbor_indices(&field, idx, *edge)
+                );
+            }
+        }
+    }
+
+    /// The cells bordering the given cell on the given edge, by their index
+    fn neighbor_indices(field: &Field, idx: NodeIndex, edge: BoxEdge) -> Vec<NodeIndex> {
+        let mut neighbors: Vec<NodeIndex> = field
+            .graph
+            .edges(idx)
+            .filter(|e| e.weight().0 == edge)
+            .map(|e| e.target())
+            .collect();
+        neighbors.sort();
+        neighbors
+    }
+
+    #[test]
+    fn large_cell_added_to_empty_field_resizes_index() {
+        let mut field = Field::new(vec![]).unwrap();
+        let big = field
+            .add_cell(Cell::new(
+                NO_SOLIDITY,
+                PhysBox::new([0.0, 0.0, 1000.0, 1000.0]).unwrap(),
+            ))
+            .unwrap();
+
+        // Indexed with buckets around its own size rather than the empty field's default
+        assert_eq!(field.index.bucket_size, 1000.0);
+        assert!(field.index.buckets.len() <= 4);
+        assert_eq!(field.cell_at_pos([500.0, 500.0].into()).unwrap().i, big);
+
+        // Small cells added afterwards eventually shrink the buckets back down
+        for x in 0..20 {
+            field
+                .add_cell(Cell::new(
+                    SOLID,
+                    PhysBox::new([1000.0 + x as f64, 0.0, 1.0, 1.0]).unwrap(),
+                ))
+                .unwrap();
+        }
+        assert!(field.index.bucket_size < 250.0);
+        assert_eq!(field.cell_at_pos([500.0, 500.0].into()).unwrap().i, big);
+        assert_eq!(
+            field
+                .cell_at_pos([1010.5, 0.5].into())
+                .unwrap()
+                .physbox
+                .left_x(),
+            1010.0
+        );
+    }
+
+    #[test]
+    fn removing_and_adding_cells_rebuilds_neighbor_edges() {
+        let mut field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([20.0, 0.0, 10.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+        let left = field.cell_at_pos([5.0, 5.0].into()).unwrap().i;
+        let middle = field.cell_at_pos([15.0, 5.0].into()).unwrap().i;
+        let right = field.cell_at_pos([25.0, 5.0].into()).unwrap().i;
+        assert_eq!(neighbor_indices(&field, left, BoxEdge::Right), vec![middle]);
+
+        let removed = field.remove_cell(middle).unwrap();
+        assert_eq!(removed.solidity, SOLID);
+        assert!(field.cell_at_pos([15.0, 5.0].into()).is_none());
+        assert!(neighbor_indices(&field, left, BoxEdge::Right).is_empty());
+        assert!(neighbor_indices(&field, right, BoxEdge::Left).is_empty());
+        // The other cells keep their indices
+        assert_eq!(field.get_cell(right).unwrap().physbox.left_x(), 20.0);
+        assert_eq!(
+            field.remove_cell(middle).unwrap_err(),
+            PhysicsError::FieldIndex(middle.index())
+        );
+
+        // Split the gap into two cells
+        let top = field
+            .add_cell(Cell::new(
+                NO_SOLIDITY,
+                PhysBox::new([10.0, 0.0, 10.0, 5.0]).unwrap(),
+            ))
+            .unwrap();
+        let bottom = field
+            .add_cell(Cell::new(
+                SOLID,
+                PhysBox::new([10.0, 5.0, 10.0, 5.0]).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(field.cell_at_pos([15.0, 2.0].into()).unwrap().i, top);
+        assert_eq!(field.cell_at_pos([15.0, 7.0].into()).unwrap().i, bottom);
+
+        let mut both = vec![top, bottom];
+        both.sort();
+        assert_eq!(neighbor_indices(&field, left, BoxEdge::Right), both);
+        assert_eq!(neighbor_indices(&field, right, BoxEdge::Left), both);
+        assert_eq!(neighbor_indices(&field, top, BoxEdge::Left), vec![left]);
+        assert_eq!(neighbor_indices(&field, top, BoxEdge::Right), vec![right]);
+        assert_eq!(neighbor_indices(&field, top, BoxEdge::Bottom), vec![bottom]);
+        assert_eq!(neighbor_indices(&field, bottom, BoxEdge::Top), vec![top]);
+    }
+
+    #[test]
+    fn new_validated_accepts_clean_grid() {
+        let (boxes, _) = create_test_field();
+        let cells = boxes
+            .into_iter()
+            .map(|b| Cell::new(NO_SOLIDITY, b))
+            .collect();
+
+        assert!(Field::new_validated(cells).is_ok());
+    }
+
+    #[test]
+    fn new_validated_rejects_overlapping_cells() {
+        let cells = vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([30.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([15.0, 5.0, 10.0, 10.0]).unwrap()),
+        ];
+
+        assert_eq!(
+            Field::new_validated(cells).unwrap_err(),
+            PhysicsError::OverlappingCells { a: 2, b: 3 }
+        );
+    }
+
+    #[test]
+    fn cells_overlapping_box_spanning_two_cells() {
+        let (boxes, field) = create_test_field();
+        // Straddles the border between cells 2 and 3
+        let physbox = PhysBox::new([35.0, 5.0, 4.0, 10.0]).unwrap();
+
+        let overlapping: Vec<PhysBox> = field
+            .cells_overlapping_box(&physbox)
+            .iter()
+            .map(|c| c.physbox)
+            .collect();
+        assert_eq!(overlapping, vec![boxes[2], boxes[3]]);
+    }
+
+    #[test]
+    fn cells_overlapping_box_inside_one_cell() {
+        let (boxes, field) = create_test_field();
+        let physbox = PhysBox::new([35.0, 20.0, 4.0, 4.0]).unwrap();
+
+        let overlapping: Vec<PhysBox> = field
+            .cells_overlapping_box(&physbox)
+            .iter()
+            .map(|c| c.physbox)
+            .collect();
+        assert_eq!(overlapping, vec![boxes[3]]);
+    }
 }
@@ -9,7 +9,7 @@ pub mod entity;
 /// The setting of a game scene, determines static obstables
 pub mod field;
 
-use crate::types::BoxEdge;
+use crate::{types::BoxEdge, ScarabError, ScarabResult};
 
 /// Represents whether the typical entity can enter/exit a cell from each side
 ///
@@ -62,6 +62,66 @@ pub const ENTER_BOTTOM: Solidity = Solidity(0b0000_0001);
 /// Bitmask for solidities that can exit on the bottom
 pub const EXIT_BOTTOM: Solidity = Solidity(0b0001_0000);
 
+/// Solidity for "jump-through" platforms: only its top edge can't be entered, so entities
+/// land on it from above but can pass through it from below or the sides.
+/// Exiting the top is still allowed so that entities jumping up through it can get out
+/// ```
+/// use scarab_engine::{
+///     gameobject::{entity::Entity, field::{Cell, Field}, NO_SOLIDITY, ONE_WAY_TOP},
+///     scene::GameTickArgs,
+///     types::physbox::{HasBox, HasBoxMut, PhysBox},
+/// };
+/// # fn main() -> scarab_engine::ScarabResult<()> {
+///
+/// let field = Field::new(vec![
+///     Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 20.0])?),
+///     Cell::new(ONE_WAY_TOP, PhysBox::new([0.0, 20.0, 10.0, 2.0])?),
+///     Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 22.0, 10.0, 20.0])?),
+/// ])?;
+/// let mut pending_attacks = Vec::new();
+/// let mut spawn_queue = Vec::new();
+/// let args = GameTickArgs::<()> {
+///     field: &field,
+///     pending_attacks: &mut pending_attacks,
+///     spawn_queue: &mut spawn_queue,
+///     dt: 1.0,
+/// };
+///
+/// // Falling onto the platform lands on it
+/// let mut falling = Entity::new()?;
+/// falling.set_max_velocity(5.0)?;
+/// falling.get_box_mut().set_pos([2.0, 10.0].into());
+/// falling.get_box_mut().set_size([4.0, 4.0].into())?;
+/// falling.set_velocity([0.0, 5.0].into());
+/// for _ in 0..5 {
+///     falling.game_tick(&args)?;
+/// }
+/// assert_eq!(falling.get_box().bottom_y(), 20.0);
+///
+/// // Rising from below passes right through it
+/// let mut rising = Entity::new()?;
+/// rising.set_max_velocity(5.0)?;
+/// rising.get_box_mut().set_pos([2.0, 30.0].into());
+/// rising.get_box_mut().set_size([4.0, 4.0].into())?;
+/// rising.set_velocity([0.0, -5.0].into());
+/// for _ in 0..5 {
+///     rising.game_tick(&args)?;
+/// }
+/// assert_eq!(rising.get_box().top_y(), 5.0);
+/// # Ok(())
+/// # }
+/// ```
+pub const ONE_WAY_TOP: Solidity = Solidity(!ENTER_TOP.0);
+/// Solidity that can be passed through from any side except into its left edge.
+/// See [ONE_WAY_TOP]
+pub const ONE_WAY_LEFT: Solidity = Solidity(!ENTER_LEFT.0);
+/// Solidity that can be passed through from any side except into its right edge.
+/// See [ONE_WAY_TOP]
+pub const ONE_WAY_RIGHT: Solidity = Solidity(!ENTER_RIGHT.0);
+/// Solidity that can be passed through from any side except into its bottom edge.
+/// See [ONE_WAY_TOP]
+pub const ONE_WAY_BOTTOM: Solidity = Solidity(!ENTER_BOTTOM.0);
+
 /// For each function true means that edge can be passed
 impl Solidity {
     /// Whether or not the left side of the attached object can be entered.
@@ -136,6 +196,72 @@ impl Solidity {
     pub fn has_solidity(&self) -> bool {
         self != &NO_SOLIDITY
     }
+
+    /// Starts building a solidity edge by edge, beginning from [SOLID]
+    /// ```
+    /// use scarab_engine::{gameobject::{Solidity, ENTER_TOP, EXIT_BOTTOM}, types::BoxEdge};
+    ///
+    /// let solidity = Solidity::builder()
+    ///     .enter(BoxEdge::Top)
+    ///     .exit(BoxEdge::Bottom)
+    ///     .build();
+    /// assert_eq!(solidity, ENTER_TOP | EXIT_BOTTOM);
+    /// ```
+    pub fn builder() -> SolidityBuilder {
+        SolidityBuilder::default()
+    }
+
+    /// The bitmask for entering the given edge
+    fn enter_mask(edge: BoxEdge) -> Solidity {
+        match edge {
+            BoxEdge::Top => ENTER_TOP,
+            BoxEdge::Left => ENTER_LEFT,
+            BoxEdge::Bottom => ENTER_BOTTOM,
+            BoxEdge::Right => ENTER_RIGHT,
+        }
+    }
+
+    /// The bitmask for exiting the given edge
+    fn exit_mask(edge: BoxEdge) -> Solidity {
+        match edge {
+            BoxEdge::Top => EXIT_TOP,
+            BoxEdge::Left => EXIT_LEFT,
+            BoxEdge::Bottom => EXIT_BOTTOM,
+            BoxEdge::Right => EXIT_RIGHT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Builds up a [Solidity] one edge at a time. See [Solidity::builder]
+pub struct SolidityBuilder(Solidity);
+
+impl Default for SolidityBuilder {
+    fn default() -> Self {
+        Self(SOLID)
+    }
+}
+
+impl SolidityBuilder {
+    /// Allows the given edge to be entered
+    pub fn enter(self, edge: BoxEdge) -> Self {
+        Self(self.0 | Solidity::enter_mask(edge))
+    }
+
+    /// Allows the given edge to be exited
+    pub fn exit(self, edge: BoxEdge) -> Self {
+        Self(self.0 | Solidity::exit_mask(edge))
+    }
+
+    /// Allows the given edge to be both entered and exited
+    pub fn passable(self, edge: BoxEdge) -> Self {
+        self.enter(edge).exit(edge)
+    }
+
+    /// The finished solidity
+    pub fn build(self) -> Solidity {
+        self.0
+    }
 }
 
 impl BitAnd<Solidity> for Solidity {
@@ -181,9 +307,19 @@ impl Health {
         }
     }
 
-    /// Apply a raw amount of damage.
+    /// Apply a raw amount of damage. Health won't go below 0
     pub fn raw_damage(&mut self, amt: Scalar) {
-        self.curr -= amt;
+        self.curr = f64::max(self.curr - amt, 0.0);
+    }
+
+    /// Restore some health. Health won't go above max
+    pub fn heal(&mut self, amt: Scalar) {
+        self.curr = f64::min(self.curr + amt, self.max);
+    }
+
+    /// Whether the health has run out
+    pub fn is_dead(&self) -> bool {
+        self.curr <= 0.0
     }
 
     /// The current health value
@@ -196,6 +332,24 @@ impl Health {
         self.max
     }
 
+    /// Sets the maximum health, which must be greater than 0.
+    /// When `keep_fraction` is true, the current health is rescaled to stay the same fraction of max.
+    /// Otherwise the current health is unchanged, except that it can't go above the new max
+    pub fn set_max(&mut self, new_max: Scalar, keep_fraction: bool) -> ScarabResult<()> {
+        if new_max <= 0.0 {
+            return Err(ScarabError::MaxHealth);
+        }
+
+        if keep_fraction {
+            self.curr = self.fraction() * new_max;
+        } else {
+            self.curr = f64::min(self.curr, new_max);
+        }
+        self.max = new_max;
+
+        Ok(())
+    }
+
     /// The current health as a fraction of max health
     pub fn fraction(&self) -> Scalar {
         self.curr / self.max
@@ -210,3 +364,107 @@ pub trait HasHealth {
     /// A mutable reference to the game object's internal health
     fn get_health_mut(&mut self) -> &mut Health;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn solidity_builder_matches_bitmasks() {
+        assert_eq!(Solidity::builder().build(), SOLID);
+
+        let all_passable = BoxEdge::iter()
+            .fold(Solidity::builder(), |b, edge| b.passable(*edge))
+            .build();
+        assert_eq!(all_passable, NO_SOLIDITY);
+
+        // Can be entered from anywhere, but only exited upward
+        let one_way = Solidity::builder()
+            .enter(BoxEdge::Left)
+            .enter(BoxEdge::Right)
+            .enter(BoxEdge::Top)
+            .enter(BoxEdge::Bottom)
+            .exit(BoxEdge::Top)
+            .build();
+        assert_eq!(
+            one_way,
+            ENTER_LEFT | ENTER_RIGHT | ENTER_TOP | ENTER_BOTTOM | EXIT_TOP
+        );
+    }
+
+    #[test]
+    fn heal_stays_at_max() {
+        let mut health = Health::new(10.0);
+        health.raw_damage(3.0);
+        health.heal(1.0);
+        assert_eq!(health.current(), 8.0);
+
+        health.heal(100.0);
+        assert_eq!(health.current(), 10.0);
+    }
+
+    #[test]
+    fn damage_stays_at_zero() {
+        let mut health = Health::new(10.0);
+        health.raw_damage(100.0);
+        assert_eq!(health.current(), 0.0);
+        assert_eq!(health.fraction(), 0.0);
+    }
+
+    #[test]
+    fn set_max_keeping_fraction_rescales_current() {
+        let mut health = Health::new(10.0);
+        health.raw_damage(5.0);
+
+        health.set_max(20.0, true).unwrap();
+        assert_eq!(health.max(), 20.0);
+        assert_eq!(health.current(), 10.0);
+
+        health.set_max(4.0, true).unwrap();
+        assert_eq!(health.current(), 2.0);
+    }
+
+    #[test]
+    fn set_max_without_keeping_fraction_clamps_current() {
+        let mut health = Health::new(10.0);
+        health.raw_damage(2.0);
+
+        health.set_max(20.0, false).unwrap();
+        assert_eq!(health.max(), 20.0);
+        assert_eq!(health.current(), 8.0);
+
+        health.set_max(5.0, false).unwrap();
+        assert_eq!(health.current(), 5.0);
+    }
+
+    #[test]
+    fn set_max_rejects_non_positive_max() {
+        let mut health = Health::new(10.0);
+
+        assert!(matches!(
+            health.set_max(0.0, true),
+            Err(ScarabError::MaxHealth)
+        ));
+        assert!(matches!(
+            health.set_max(-1.0, false),
+            Err(ScarabError::MaxHealth)
+        ));
+        assert_eq!(health.max(), 10.0);
+        assert_eq!(health.current(), 10.0);
+    }
+
+    #[test]
+    fn is_dead_once_health_runs_out() {
+        let mut health = Health::new(10.0);
+        assert!(!health.is_dead());
+
+        health.raw_damage(9.0);
+        assert!(!health.is_dead());
+
+        health.raw_damage(1.0);
+        assert!(health.is_dead());
+
+        health.heal(1.0);
+        assert!(!health.is_dead());
+    }
+}
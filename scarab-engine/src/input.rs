@@ -1,8 +1,15 @@
+use std::{
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
 use piston::{
-    Button, ButtonArgs, ButtonState, ControllerButton, ControllerHat, HatState, Input, Key,
+    Button, ButtonArgs, ButtonState, ControllerButton, ControllerHat, HatState, Input, Key, Motion,
     MouseButton,
 };
 use serde::{Deserialize, Serialize};
+use shapes::Point;
 
 use crate::{types::ROOT_2, ScarabResult};
 
@@ -35,6 +42,12 @@ pub trait InputBinding {
 
     /// If the given input matches this binding, returns the result corresponding to the input
     fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg>;
+
+    /// Advances the binding by `dt` seconds, for bindings that act over time rather than only on input events.
+    /// Does nothing by default
+    fn tick(&mut self, _dt: f64) -> Option<Self::ActionArg> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,7 +91,8 @@ impl PartialEq<SingleButton> for Button {
 pub enum UnitAxis2dBinding {
     /// A unit circle binding made up of a logical d-pad. This can only generate inputs on the circle every 45 degrees.
     LogicalDpad(LogicalDpad),
-    // TODO: add support for joysticks etc.
+    /// A unit circle binding made up of two analog controller axes, i.e. a joystick
+    JoystickAxis2d(JoystickAxis2d),
 }
 
 impl InputBinding for UnitAxis2dBinding {
@@ -87,10 +101,17 @@ impl InputBinding for UnitAxis2dBinding {
     fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg> {
         match self {
             UnitAxis2dBinding::LogicalDpad(dpad) => dpad.maybe_to_action(input),
+            UnitAxis2dBinding::JoystickAxis2d(joystick) => joystick.maybe_to_action(input),
         }
     }
 }
 
+impl From<JoystickAxis2d> for UnitAxis2dBinding {
+    fn from(value: JoystickAxis2d) -> Self {
+        UnitAxis2dBinding::JoystickAxis2d(value)
+    }
+}
+
 impl From<LogicalDpad> for UnitAxis2dBinding {
     fn from(value: LogicalDpad) -> Self {
         UnitAxis2dBinding::LogicalDpad(value)
@@ -159,6 +180,60 @@ fn hat_state_to_action_arg(value: HatState) -> [f64; 2] {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Two analog axes on a controller combined into a 2-d input, i.e. a joystick.
+/// Inputs within the deadzone of the center are zeroed and inputs outside of the unit circle are clamped to it
+pub struct JoystickAxis2d {
+    id: u32,
+    x: (u8, f64),
+    y: (u8, f64),
+    deadzone: f64,
+}
+
+impl JoystickAxis2d {
+    /// Makes a new instance of self for the given controller and its axes
+    pub fn new(id: u32, x_axis: u8, y_axis: u8, deadzone: f64) -> Self {
+        Self {
+            id,
+            x: (x_axis, 0.0),
+            y: (y_axis, 0.0),
+            deadzone,
+        }
+    }
+
+    fn current_value(&self) -> [f64; 2] {
+        let [x, y] = [self.x.1, self.y.1];
+        let magnitude = f64::sqrt(x * x + y * y);
+        if magnitude <= self.deadzone {
+            [0.0, 0.0]
+        } else if magnitude > 1.0 {
+            [x / magnitude, y / magnitude]
+        } else {
+            [x, y]
+        }
+    }
+}
+
+impl InputBinding for JoystickAxis2d {
+    type ActionArg = [f64; 2];
+
+    fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg> {
+        if let Input::Move(Motion::ControllerAxis(args)) = input {
+            if args.id == self.id {
+                if args.axis == self.x.0 {
+                    self.x.1 = args.position;
+                    return Some(self.current_value());
+                } else if args.axis == self.y.0 {
+                    self.y.1 = args.position;
+                    return Some(self.current_value());
+                }
+            }
+        }
+
+        None
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A logical representation of a 2-axis D-pad
 pub struct VirtualDpad {
@@ -296,3 +371,656 @@ impl InputBinding for ButtonBinding {
         None
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An input binding for pressing an ordered sequence of buttons, i.e. a fighting game combo.
+/// Each press must come within `window_ms` of the previous one. Pressing the wrong button or
+/// waiting too long starts the sequence over
+pub struct SequenceBinding {
+    steps: Vec<SingleButton>,
+    window_ms: f64,
+    #[serde(skip)]
+    cursor: usize,
+    #[serde(skip)]
+    last_step: Option<Instant>,
+}
+
+impl SequenceBinding {
+    /// Makes a new instance of self for the given sequence of buttons
+    pub fn new(steps: Vec<SingleButton>, window_ms: f64) -> Self {
+        Self {
+            steps,
+            window_ms,
+            cursor: 0,
+            last_step: None,
+        }
+    }
+
+    /// Handles the input as if it happened at `now`
+    fn maybe_to_action_at(&mut self, input: &Input, now: Instant) -> Option<bool> {
+        let args = match input {
+            Input::Button(args) if args.state == ButtonState::Press => args,
+            _ => return None,
+        };
+
+        let window = Duration::from_secs_f64(self.window_ms / 1000.0);
+        if self
+            .last_step
+            .is_some_and(|last| now.duration_since(last) > window)
+        {
+            self.cursor = 0;
+        }
+
+        if self
+            .steps
+            .get(self.cursor)
+            .is_some_and(|b| b == &args.button)
+        {
+            self.cursor += 1;
+        } else if self.steps.first().is_some_and(|b| b == &args.button) {
+            // The wrong button might still start the sequence over
+            self.cursor = 1;
+        } else {
+            self.cursor = 0;
+        }
+        self.last_step = Some(now);
+
+        if self.cursor > 0 && self.cursor == self.steps.len() {
+            self.cursor = 0;
+            self.last_step = None;
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+impl InputBinding for SequenceBinding {
+    type ActionArg = bool;
+
+    fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg> {
+        self.maybe_to_action_at(input, Instant::now())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An input binding that repeats while its button is held, i.e. for automatic weapons.
+/// Pressing the button is true right away, then [InputBinding::tick] is true every `interval_ms` until it's released.
+/// Presses while the button is already held (i.e. from OS key repeat) are ignored
+pub struct RepeatBinding {
+    button: SingleButton,
+    interval_ms: f64,
+    #[serde(skip)]
+    held: bool,
+    #[serde(skip)]
+    held_ms: f64,
+}
+
+impl RepeatBinding {
+    /// Makes a new instance of self for the given button and repeat interval
+    pub fn new(button: SingleButton, interval_ms: f64) -> Self {
+        Self {
+            button,
+            interval_ms,
+            held: false,
+            held_ms: 0.0,
+        }
+    }
+}
+
+impl InputBinding for RepeatBinding {
+    type ActionArg = bool;
+
+    fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg> {
+        if let Input::Button(args) = input {
+            if args.button == self.button {
+                let pressed = args.state == ButtonState::Press;
+                // The OS sends repeated presses while the button is held, which shouldn't restart the interval
+                if pressed && self.held {
+                    return None;
+                }
+                self.held = pressed;
+                self.held_ms = 0.0;
+                return Some(pressed);
+            }
+        }
+
+        None
+    }
+
+    /// True if an interval ended during this tick. Several intervals ending in one tick still only count once
+    fn tick(&mut self, dt: f64) -> Option<Self::ActionArg> {
+        if !self.held || self.interval_ms <= 0.0 {
+            return None;
+        }
+
+        self.held_ms += dt * 1000.0;
+        if self.held_ms >= self.interval_ms {
+            self.held_ms %= self.interval_ms;
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+/// An input binding for the position of the mouse cursor on the screen.
+/// To aim at the cursor in the world, pass the point to [Camera::screen_to_world](crate::rendering::Camera::screen_to_world)
+pub struct MousePositionBinding;
+
+impl InputBinding for MousePositionBinding {
+    type ActionArg = Point;
+
+    fn maybe_to_action(&mut self, input: &Input) -> Option<Self::ActionArg> {
+        if let Input::Move(Motion::MouseCursor(pos)) = input {
+            return Some((*pos).into());
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "A: Serialize, B: Serialize",
+    deserialize = "A: Deserialize<'de> + Eq + Hash, B: Deserialize<'de>"
+))]
+/// Maps actions to the button-like bindings that trigger them, so they can be remapped at runtime
+/// (i.e. from a settings menu) and saved along with the rest of the game's settings
+pub struct KeyMap<A: Eq + Hash, B = ButtonBinding> {
+    /// Kept in the order the actions were first bound, so that inputs are mapped the same way every run
+    bindings: IndexMap<A, B>,
+}
+
+impl<A: Eq + Hash, B> Default for KeyMap<A, B> {
+    fn default() -> Self {
+        Self {
+            bindings: IndexMap::new(),
+        }
+    }
+}
+
+impl<A, B> KeyMap<A, B>
+where
+    A: Eq + Hash,
+    B: InputBinding<ActionArg = bool>,
+{
+    /// Makes a new key map with nothing bound
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the action to the binding, returning the action's previous binding if it had one.
+    /// Rebinding an action keeps its place in the binding order
+    pub fn bind(&mut self, action: A, binding: B) -> Option<B> {
+        self.bindings.insert(action, binding)
+    }
+
+    /// Removes the action's binding, returning it if there was one
+    pub fn unbind(&mut self, action: &A) -> Option<B> {
+        self.bindings.shift_remove(action)
+    }
+
+    /// Gets the action's binding if it has one
+    pub fn get(&self, action: &A) -> Option<&B> {
+        self.bindings.get(action)
+    }
+
+    /// Gives the input to every binding, so that bindings which track several inputs (i.e. [SequenceBinding])
+    /// see all of them. Returns all of the actions whose binding matched the input, in the order they were bound
+    pub fn map_input(&mut self, input: &Input) -> Vec<(&A, bool)> {
+        self.bindings
+            .iter_mut()
+            .filter_map(|(action, binding)| {
+                binding.maybe_to_action(input).map(|arg| (&*action, arg))
+            })
+            .collect()
+    }
+
+    /// Ticks every binding (see [InputBinding::tick]), returning all of the actions that produced a result
+    pub fn tick(&mut self, dt: f64) -> Vec<(&A, bool)> {
+        self.bindings
+            .iter_mut()
+            .filter_map(|(action, binding)| binding.tick(dt).map(|arg| (&*action, arg)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// An input that an [InputRecorder] saw, along with the frame it arrived on
+pub struct RecordedInput {
+    /// The frame the input arrived on, i.e. [Scene::fixed_step_count](crate::scene::Scene::fixed_step_count)
+    pub frame: u64,
+    /// The raw input event
+    pub input: Input,
+}
+
+/// Wraps an [InputRegistry], recording every input it's given so that it can be replayed by an [InputPlayer]
+#[derive(Debug)]
+pub struct InputRecorder<R> {
+    inner: R,
+    frame: u64,
+    recording: Vec<RecordedInput>,
+}
+
+impl<R> InputRecorder<R> {
+    /// Starts recording the inputs given to `inner` on frame 0
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            frame: 0,
+            recording: Vec::new(),
+        }
+    }
+
+    /// Sets the frame that the following inputs are recorded on
+    pub fn set_frame(&mut self, frame: u64) {
+        self.frame = frame;
+    }
+
+    /// Every input recorded so far, in the order they arrived
+    pub fn recording(&self) -> &[RecordedInput] {
+        &self.recording
+    }
+
+    /// Stops recording, returning the wrapped registry and the recorded inputs
+    pub fn finish(self) -> (R, Vec<RecordedInput>) {
+        (self.inner, self.recording)
+    }
+}
+
+impl<R: InputRegistry> InputRegistry for InputRecorder<R> {
+    type InputActions = R::InputActions;
+    type InputTarget = R::InputTarget;
+
+    fn do_input_action(
+        &self,
+        action: Self::InputActions,
+        target: &mut Self::InputTarget,
+    ) -> ScarabResult<()> {
+        self.inner.do_input_action(action, target)
+    }
+
+    fn map_input_to_action(&mut self, input: &Input) -> Option<Self::InputActions> {
+        self.recording.push(RecordedInput {
+            frame: self.frame,
+            input: input.clone(),
+        });
+        self.inner.map_input_to_action(input)
+    }
+}
+
+/// Wraps an [InputRegistry], feeding it the inputs from an [InputRecorder]'s recording
+/// in place of any live input
+#[derive(Debug)]
+pub struct InputPlayer<R> {
+    inner: R,
+    recording: Vec<RecordedInput>,
+    next: usize,
+}
+
+impl<R: InputRegistry> InputPlayer<R> {
+    /// Plays back `recording` through `inner`
+    pub fn new(inner: R, recording: Vec<RecordedInput>) -> Self {
+        Self {
+            inner,
+            recording,
+            next: 0,
+        }
+    }
+
+    /// Maps each recorded input up to and including `frame` that hasn't been played yet,
+    /// in the order they were recorded
+    pub fn actions_for_frame(&mut self, frame: u64) -> Vec<R::InputActions> {
+        let mut actions = Vec::new();
+        while let Some(recorded) = self.recording.get(self.next) {
+            if recorded.frame > frame {
+                break;
+            }
+            if let Some(action) = self.inner.map_input_to_action(&recorded.input) {
+                actions.push(action);
+            }
+            self.next += 1;
+        }
+        actions
+    }
+
+    /// Whether every recorded input has been played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.len()
+    }
+}
+
+impl<R: InputRegistry> InputRegistry for InputPlayer<R> {
+    type InputActions = R::InputActions;
+    type InputTarget = R::InputTarget;
+
+    fn do_input_action(
+        &self,
+        action: Self::InputActions,
+        target: &mut Self::InputTarget,
+    ) -> ScarabResult<()> {
+        self.inner.do_input_action(action, target)
+    }
+
+    /// Live inputs are ignored during playback, see [InputPlayer::actions_for_frame]
+    fn map_input_to_action(&mut self, _input: &Input) -> Option<Self::InputActions> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use piston::ControllerAxisArgs;
+
+    use super::*;
+
+    fn press(key: Key) -> Input {
+        Input::Button(ButtonArgs {
+            state: ButtonState::Press,
+            button: Button::Keyboard(key),
+            scancode: None,
+        })
+    }
+
+    fn combo() -> SequenceBinding {
+        SequenceBinding::new(
+            vec![
+                SingleButton::Keyboard(Key::S),
+                SingleButton::Keyboard(Key::D),
+                SingleButton::Keyboard(Key::J),
+            ],
+            200.0,
+        )
+    }
+
+    fn axis_input(id: u32, axis: u8, position: f64) -> Input {
+        Input::Move(Motion::ControllerAxis(ControllerAxisArgs::new(
+            id, axis, position,
+        )))
+    }
+
+    #[test]
+    fn joystick_zeroes_deadzone() {
+        let mut joystick: UnitAxis2dBinding = JoystickAxis2d::new(0, 0, 1, 0.2).into();
+
+        assert_eq!(
+            joystick.maybe_to_action(&axis_input(0, 0, 0.1)),
+            Some([0.0, 0.0])
+        );
+        assert_eq!(
+            joystick.maybe_to_action(&axis_input(0, 1, -0.1)),
+            Some([0.0, 0.0])
+        );
+        assert_eq!(
+            joystick.maybe_to_action(&axis_input(0, 1, -0.5)),
+            Some([0.1, -0.5])
+        );
+        // Other controllers and axes are ignored
+        assert_eq!(joystick.maybe_to_action(&axis_input(1, 0, 0.5)), None);
+        assert_eq!(joystick.maybe_to_action(&axis_input(0, 2, 0.5)), None);
+    }
+
+    #[test]
+    fn joystick_clamps_to_unit_circle() {
+        let mut joystick = JoystickAxis2d::new(0, 0, 1, 0.2);
+
+        joystick.maybe_to_action(&axis_input(0, 0, 1.0));
+        let [x, y] = joystick.maybe_to_action(&axis_input(0, 1, 1.0)).unwrap();
+        assert!((x - 1.0 / f64::sqrt(2.0)).abs() < 1e-9);
+        assert!((y - 1.0 / f64::sqrt(2.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sequence_completes_once() {
+        let mut sequence = combo();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        assert_eq!(sequence.maybe_to_action_at(&press(Key::S), at(0)), None);
+        assert_eq!(sequence.maybe_to_action_at(&press(Key::D), at(100)), None);
+        assert_eq!(
+            sequence.maybe_to_action_at(&press(Key::J), at(200)),
+            Some(true)
+        );
+        // Starts over after completing
+        assert_eq!(sequence.maybe_to_action_at(&press(Key::J), at(250)), None);
+    }
+
+    #[test]
+    fn slow_sequence_resets() {
+        let mut sequence = combo();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        sequence.maybe_to_action_at(&press(Key::S), at(0));
+        sequence.maybe_to_action_at(&press(Key::D), at(100));
+        assert_eq!(sequence.maybe_to_action_at(&press(Key::J), at(400)), None);
+
+        sequence.maybe_to_action_at(&press(Key::S), at(500));
+        sequence.maybe_to_action_at(&press(Key::D), at(600));
+        assert_eq!(
+            sequence.maybe_to_action_at(&press(Key::J), at(700)),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn wrong_button_resets_sequence() {
+        let mut sequence = combo();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        sequence.maybe_to_action_at(&press(Key::S), at(0));
+        sequence.maybe_to_action_at(&press(Key::A), at(50));
+        assert_eq!(sequence.maybe_to_action_at(&press(Key::D), at(100)), None);
+        assert_eq!(sequence.maybe_to_action_at(&press(Key::J), at(150)), None);
+
+        // Releases don't count as the wrong button
+        sequence.maybe_to_action_at(&press(Key::S), at(200));
+        let release = Input::Button(ButtonArgs {
+            state: ButtonState::Release,
+            button: Button::Keyboard(Key::S),
+            scancode: None,
+        });
+        assert_eq!(sequence.maybe_to_action_at(&release, at(220)), None);
+        sequence.maybe_to_action_at(&press(Key::D), at(250));
+        assert_eq!(
+            sequence.maybe_to_action_at(&press(Key::J), at(300)),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn mouse_position_reads_cursor() {
+        let mut binding = MousePositionBinding;
+
+        assert_eq!(
+            binding.maybe_to_action(&Input::Move(Motion::MouseCursor([12.0, 34.5]))),
+            Some([12.0, 34.5].into())
+        );
+        assert_eq!(
+            binding.maybe_to_action(&Input::Move(Motion::MouseRelative([1.0, 1.0]))),
+            None
+        );
+        assert_eq!(binding.maybe_to_action(&press(Key::S)), None);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestActions {
+        Jump,
+        Attack,
+    }
+
+    #[test]
+    fn key_map_remaps_actions() {
+        let mut key_map = KeyMap::new();
+        key_map.bind(
+            TestActions::Jump,
+            ButtonBinding::new(ButtonState::Press, SingleButton::Keyboard(Key::Space)),
+        );
+        key_map.bind(
+            TestActions::Attack,
+            ButtonBinding::new(ButtonState::Press, SingleButton::Keyboard(Key::J)),
+        );
+        assert_eq!(
+            key_map.map_input(&press(Key::Space)),
+            vec![(&TestActions::Jump, true)]
+        );
+
+        let previous = key_map.bind(
+            TestActions::Jump,
+            ButtonBinding::new(ButtonState::Press, SingleButton::Keyboard(Key::K)),
+        );
+        assert!(previous.is_some());
+        assert!(key_map.map_input(&press(Key::Space)).is_empty());
+        assert_eq!(
+            key_map.map_input(&press(Key::K)),
+            vec![(&TestActions::Jump, true)]
+        );
+        assert_eq!(
+            key_map.map_input(&press(Key::J)),
+            vec![(&TestActions::Attack, true)]
+        );
+
+        // Remaps are kept when saved and loaded
+        let serialized = serde_json::to_string(&key_map).unwrap();
+        let mut loaded: KeyMap<TestActions> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(
+            loaded.map_input(&press(Key::K)),
+            vec![(&TestActions::Jump, true)]
+        );
+
+        assert!(loaded.unbind(&TestActions::Jump).is_some());
+        assert!(loaded.map_input(&press(Key::K)).is_empty());
+    }
+
+    #[test]
+    fn key_map_gives_input_to_every_binding() {
+        let mut key_map = KeyMap::new();
+        key_map.bind(
+            TestActions::Jump,
+            SequenceBinding::new(vec![SingleButton::Keyboard(Key::J)], 200.0),
+        );
+        key_map.bind(TestActions::Attack, combo());
+
+        // The combo still sees every key even though J also matches the jump binding
+        assert!(key_map.map_input(&press(Key::S)).is_empty());
+        assert!(key_map.map_input(&press(Key::D)).is_empty());
+        assert_eq!(
+            key_map.map_input(&press(Key::J)),
+            vec![(&TestActions::Jump, true), (&TestActions::Attack, true)]
+        );
+    }
+
+    /// Maps inputs with a [KeyMap], pushing each action onto its target
+    struct TestRegistry(KeyMap<TestActions>);
+
+    impl InputRegistry for TestRegistry {
+        type InputActions = (TestActions, bool);
+        type InputTarget = Vec<(TestActions, bool)>;
+
+        fn do_input_action(
+            &self,
+            action: Self::InputActions,
+            target: &mut Self::InputTarget,
+        ) -> ScarabResult<()> {
+            target.push(action);
+            Ok(())
+        }
+
+        fn map_input_to_action(&mut self, input: &Input) -> Option<Self::InputActions> {
+            self.0.map_input(input).first().map(|&(a, held)| (*a, held))
+        }
+    }
+
+    fn test_registry() -> TestRegistry {
+        let mut key_map = KeyMap::new();
+        key_map.bind(
+            TestActions::Jump,
+            ButtonBinding::new(ButtonState::Press, SingleButton::Keyboard(Key::Space)),
+        );
+        key_map.bind(
+            TestActions::Attack,
+            ButtonBinding::new(ButtonState::Press, SingleButton::Keyboard(Key::J)),
+        );
+        TestRegistry(key_map)
+    }
+
+    #[test]
+    fn playback_reproduces_recorded_actions() {
+        let inputs = [
+            (0, press(Key::Space)),
+            (0, press(Key::A)),
+            (2, press(Key::J)),
+            (5, press(Key::Space)),
+        ];
+        let mut recorder = InputRecorder::new(test_registry());
+        let mut recorded_actions = Vec::new();
+        for (frame, input) in &inputs {
+            recorder.set_frame(*frame);
+            if let Some(action) = recorder.map_input_to_action(input) {
+                recorder
+                    .do_input_action(action, &mut recorded_actions)
+                    .unwrap();
+            }
+        }
+        // Inputs that don't map to an action are still recorded
+        assert_eq!(recorder.recording().len(), 4);
+
+        let (_, recording) = recorder.finish();
+        let serialized = serde_json::to_string(&recording).unwrap();
+        let recording: Vec<RecordedInput> = serde_json::from_str(&serialized).unwrap();
+
+        let mut player = InputPlayer::new(test_registry(), recording);
+        assert_eq!(player.map_input_to_action(&press(Key::J)), None);
+        let mut played_actions = Vec::new();
+        for frame in 0..6 {
+            let actions = player.actions_for_frame(frame);
+            match frame {
+                0 => assert_eq!(actions, vec![(TestActions::Jump, true)]),
+                2 => assert_eq!(actions, vec![(TestActions::Attack, true)]),
+                5 => assert_eq!(actions, vec![(TestActions::Jump, true)]),
+                _ => assert!(actions.is_empty()),
+            }
+            for action in actions {
+                player.do_input_action(action, &mut played_actions).unwrap();
+            }
+        }
+        assert!(player.is_finished());
+        assert_eq!(played_actions, recorded_actions);
+    }
+
+    #[test]
+    fn repeat_binding_fires_while_held() {
+        let mut binding = RepeatBinding::new(SingleButton::Keyboard(Key::J), 100.0);
+        let release = Input::Button(ButtonArgs {
+            state: ButtonState::Release,
+            button: Button::Keyboard(Key::J),
+            scancode: None,
+        });
+
+        // Nothing repeats before the button is pressed
+        assert_eq!(binding.tick(0.5), None);
+
+        assert_eq!(binding.maybe_to_action(&press(Key::J)), Some(true));
+        let fired = (0..10).filter_map(|_| binding.tick(0.04)).count();
+        assert_eq!(fired, 4);
+
+        assert_eq!(binding.maybe_to_action(&release), Some(false));
+        assert_eq!((0..10).filter_map(|_| binding.tick(0.04)).count(), 0);
+    }
+
+    #[test]
+    fn repeat_binding_ignores_os_key_repeat() {
+        let mut binding = RepeatBinding::new(SingleButton::Keyboard(Key::J), 100.0);
+
+        assert_eq!(binding.maybe_to_action(&press(Key::J)), Some(true));
+        assert_eq!(binding.tick(0.06), None);
+        // A repeated press while held neither fires nor restarts the interval
+        assert_eq!(binding.maybe_to_action(&press(Key::J)), None);
+        assert_eq!(binding.maybe_to_action(&press(Key::J)), None);
+        assert_eq!(binding.tick(0.06), Some(true));
+    }
+}
@@ -8,12 +8,20 @@
 /// The trait for running an app at a high level
 mod app;
 
+/// Selecting and applying effects on entities
+pub mod effect;
 /// Common error and result types
 pub mod error;
 /// Game objects
 pub mod gameobject;
 /// Player input
 pub mod input;
+#[cfg(feature = "persistence")]
+/// Versioned saving and loading of game state
+pub mod persistence;
+#[cfg(feature = "profiling")]
+/// Timings of the engine's systems
+pub mod profiling;
 /// Rendering everything
 pub mod rendering;
 /// The scene wrapping game objects
@@ -21,6 +29,9 @@ pub mod scene;
 /// Generic types
 pub mod types;
 
+#[cfg(test)]
+mod test_utils;
+
 pub use app::App;
 pub use error::{PhysicsError, PhysicsResult, ScarabError, ScarabResult};
 pub use glutin_window::GlutinWindow;
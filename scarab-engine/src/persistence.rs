@@ -0,0 +1,56 @@
+use std::io::{Read, Write};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{ScarabError, ScarabResult};
+
+/// Writes `version` followed by `value` to `writer` as MessagePack
+pub fn write_versioned<T, W>(mut writer: W, version: &str, value: &T) -> ScarabResult<()>
+where
+    T: Serialize,
+    W: Write,
+{
+    rmp_serde::encode::write(&mut writer, version)?;
+    rmp_serde::encode::write(&mut writer, value)?;
+    Ok(())
+}
+
+/// Reads a value written by [write_versioned], failing without reading the value
+/// if the saved version isn't exactly `version`
+pub fn read_versioned<T, R>(mut reader: R, version: &str) -> ScarabResult<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    let saved_version: String = rmp_serde::from_read(&mut reader)?;
+    if saved_version != version {
+        return Err(ScarabError::SaveVersion {
+            found: saved_version,
+            expected: version.to_string(),
+        });
+    }
+
+    Ok(rmp_serde::from_read(&mut reader)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn version_mismatch_is_rejected() {
+        let mut buf = Vec::new();
+        write_versioned(&mut buf, "1.0.0", &[1, 2, 3]).unwrap();
+
+        let loaded: Vec<i32> = read_versioned(buf.as_slice(), "1.0.0").unwrap();
+        assert_eq!(loaded, vec![1, 2, 3]);
+
+        match read_versioned::<Vec<i32>, _>(buf.as_slice(), "2.0.0") {
+            Err(ScarabError::SaveVersion { found, expected }) => {
+                assert_eq!(found, "1.0.0");
+                assert_eq!(expected, "2.0.0");
+            }
+            other => panic!("expected a version mismatch, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::{collections::HashMap, time::Duration};
+
+/// The section timing the whole of [crate::scene::Scene::tick_entities]
+pub const TICK_ENTITIES: &str = "tick_entities";
+/// The section timing the per-entity game ticks
+pub const ENTITY_TICKS: &str = "entity_ticks";
+/// The section timing entity-entity collision resolution
+pub const ENTITY_COLLISIONS: &str = "entity_collisions";
+/// The section timing the processing of pending effects
+pub const PENDING_EFFECTS: &str = "pending_effects";
+/// The section timing [crate::scene::Scene::render] (or `render_with_info`)
+pub const RENDER: &str = "render";
+
+#[derive(Debug, Clone, Default)]
+/// The most recent durations of each named section of the engine
+pub struct FrameProfile {
+    sections: HashMap<&'static str, Duration>,
+}
+
+impl FrameProfile {
+    /// Records the duration for a section, replacing its previous duration
+    pub fn record(&mut self, name: &'static str, duration: Duration) {
+        self.sections.insert(name, duration);
+    }
+
+    /// The last recorded duration of the section if it's been recorded
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.sections.get(name).copied()
+    }
+
+    /// Iterates across all recorded sections and their durations
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Duration)> + '_ {
+        self.sections
+            .iter()
+            .map(|(name, duration)| (*name, *duration))
+    }
+}
@@ -0,0 +1,206 @@
+use graphics::{types::Scalar, Context};
+use opengl_graphics::GlGraphics;
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use super::text::TextView;
+use crate::{error::RenderResult, rendering::Camera, scene::EffectHit, types::physbox::PhysBox};
+
+/// How long damage numbers are shown for (in seconds)
+const DAMAGE_NUMBER_LIFETIME: f64 = 0.8;
+/// How fast damage numbers rise (in pixels per second)
+const DAMAGE_NUMBER_RISE_SPEED: f64 = 12.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Short-lived text that rises and fades out over its lifetime
+pub struct FloatingText {
+    /// The displayed text
+    pub text: String,
+    pos: Point,
+    lifetime: f64,
+    age: f64,
+    rise_speed: f64,
+}
+
+impl FloatingText {
+    /// Creates floating text at `pos` that lasts for `lifetime` seconds,
+    /// rising `rise_speed` pixels per second
+    pub fn new(text: String, pos: Point, lifetime: f64, rise_speed: f64) -> Self {
+        Self {
+            text,
+            pos,
+            lifetime,
+            age: 0.0,
+            rise_speed,
+        }
+    }
+
+    /// Creates a damage number just above the top center of the hit target
+    pub fn damage_number(damage: Scalar, target: &PhysBox) -> Self {
+        Self::new(
+            format!("{damage}"),
+            [target.center().x, target.top_y()].into(),
+            DAMAGE_NUMBER_LIFETIME,
+            DAMAGE_NUMBER_RISE_SPEED,
+        )
+    }
+
+    /// Creates a damage number for an effect hit that took health from its target,
+    /// or `None` if the hit did no damage
+    pub fn from_effect_hit(hit: &EffectHit) -> Option<Self> {
+        (hit.damage > 0.0).then(|| Self::damage_number(hit.damage, &hit.target_box))
+    }
+
+    /// Rises and ages the text
+    pub fn update(&mut self, dt: f64) {
+        // up is negative y
+        self.pos.y -= self.rise_speed * dt;
+        self.age += dt;
+    }
+
+    /// The current position of the text in world coordinates
+    pub fn pos(&self) -> Point {
+        self.pos
+    }
+
+    /// The text's opacity, fading from 1 to 0 over its lifetime
+    pub fn alpha(&self) -> Scalar {
+        if self.lifetime <= 0.0 {
+            0.0
+        } else {
+            (1.0 - self.age / self.lifetime).clamp(0.0, 1.0)
+        }
+    }
+
+    /// Whether the text has outlived its lifetime and should be removed
+    pub fn is_expired(&self) -> bool {
+        self.age >= self.lifetime
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// Updates and renders all active [FloatingText], removing them once they expire
+pub struct FloatingTexts {
+    texts: Vec<FloatingText>,
+    view: TextView,
+}
+
+impl FloatingTexts {
+    /// Creates an empty set of floating texts, all rendered with the given view
+    pub fn new(view: TextView) -> Self {
+        Self {
+            texts: Vec::new(),
+            view,
+        }
+    }
+
+    /// Adds a new floating text
+    pub fn spawn(&mut self, text: FloatingText) {
+        self.texts.push(text);
+    }
+
+    /// Updates all of the texts, removing the ones that have expired
+    pub fn update(&mut self, dt: f64) {
+        for text in &mut self.texts {
+            text.update(dt);
+        }
+        self.texts.retain(|text| !text.is_expired());
+    }
+
+    /// Iterates across the active texts
+    pub fn iter(&self) -> core::slice::Iter<'_, FloatingText> {
+        self.texts.iter()
+    }
+
+    /// The number of active texts
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Whether there are no active texts
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    /// Renders all of the active texts, faded according to their age
+    pub fn render(
+        &mut self,
+        camera: &Camera,
+        ctx: Context,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        for text in &self.texts {
+            let mut color = self.view.color;
+            color[3] *= text.alpha() as f32;
+            self.view
+                .render_text_colored(&text.text, color, text.pos, camera, ctx, gl)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn floating_text_rises_and_fades() {
+        let mut text = FloatingText::new("5".to_string(), [10.0, 10.0].into(), 1.0, 4.0);
+        assert_eq!(text.alpha(), 1.0);
+
+        let mut last_y = text.pos().y;
+        let mut last_alpha = text.alpha();
+        for _ in 0..3 {
+            text.update(0.25);
+            assert!(text.pos().y < last_y);
+            assert!(text.alpha() < last_alpha);
+            assert!(!text.is_expired());
+            last_y = text.pos().y;
+            last_alpha = text.alpha();
+        }
+        assert_eq!(text.pos(), [10.0, 7.0].into());
+    }
+
+    #[test]
+    fn effect_hits_make_damage_numbers() {
+        let mut hit = EffectHit {
+            source: None,
+            target: uuid::Uuid::new_v4(),
+            damage: 2.5,
+            target_box: PhysBox::new([10.0, 20.0, 4.0, 6.0]).unwrap(),
+        };
+        let number = FloatingText::from_effect_hit(&hit).unwrap();
+        assert_eq!(number.text, "2.5");
+        assert_eq!(number.pos(), [12.0, 20.0].into());
+
+        hit.damage = 0.0;
+        assert_eq!(FloatingText::from_effect_hit(&hit), None);
+        hit.damage = -1.0;
+        assert_eq!(FloatingText::from_effect_hit(&hit), None);
+    }
+
+    #[test]
+    fn floating_texts_removes_expired() {
+        let mut texts =
+            FloatingTexts::new(TextView::new("font.ttf".into(), 12, [1.0, 1.0, 1.0, 1.0]));
+        let target = PhysBox::new([0.0, 10.0, 4.0, 4.0]).unwrap();
+        texts.spawn(FloatingText::damage_number(3.0, &target));
+        texts.spawn(FloatingText::new(
+            "long".to_string(),
+            [0.0, 0.0].into(),
+            DAMAGE_NUMBER_LIFETIME * 2.0,
+            1.0,
+        ));
+
+        let number = texts.iter().next().unwrap();
+        assert_eq!(number.text, "3");
+        assert_eq!(number.pos(), [2.0, 10.0].into());
+
+        texts.update(DAMAGE_NUMBER_LIFETIME);
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts.iter().next().unwrap().text, "long");
+
+        texts.update(DAMAGE_NUMBER_LIFETIME);
+        assert!(texts.is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+use core::marker::PhantomData;
+
+use derivative::Derivative;
+use graphics::types::{Color, Scalar};
+use opengl_graphics::GlGraphics;
+use piston::RenderArgs;
+use serde::{Deserialize, Serialize};
+
+use super::progress_bar::{inset_left_to_right, InsetPosition};
+use crate::{
+    error::RenderResult,
+    gameobject::HasHealth,
+    rendering::{registry::TextureRegistry, Camera, View},
+    types::physbox::HasBox,
+};
+
+#[derive(Derivative, Serialize, Deserialize)]
+#[derivative(Clone, Debug)]
+/// Renders a bar inset in the viewed game object's box that fills from left to right with its health
+pub struct HealthBarView<E> {
+    /// The color of the filled portion of the bar
+    pub color: Color,
+    /// The color of the empty portion of the bar
+    pub bg_color: Color,
+    /// The space between the bar and the edges of the game object's box
+    pub border: Scalar,
+    /// Where the bar sits vertically within the game object's box
+    pub position: InsetPosition,
+    /// The height of the bar as a fraction of the box's height (minus the border)
+    pub height_fraction: Scalar,
+    #[derivative(Debug = "ignore")]
+    phantom: PhantomData<E>,
+}
+
+impl<E> HealthBarView<E> {
+    /// Creates a new health bar view
+    pub fn new(
+        color: Color,
+        bg_color: Color,
+        border: Scalar,
+        position: InsetPosition,
+        height_fraction: Scalar,
+    ) -> Self {
+        Self {
+            color,
+            bg_color,
+            border,
+            position,
+            height_fraction,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The rectangle of the whole bar inset in `outer_rect`
+    pub fn background_rect(&self, outer_rect: &[Scalar; 4]) -> [Scalar; 4] {
+        inset_left_to_right(
+            outer_rect,
+            self.border,
+            self.height_fraction,
+            1.0,
+            self.position,
+        )
+    }
+}
+
+impl<E: HasHealth> HealthBarView<E> {
+    /// The rectangle of the filled portion of the bar inset in `outer_rect`
+    pub fn fill_rect(&self, viewed: &E, outer_rect: &[Scalar; 4]) -> [Scalar; 4] {
+        inset_left_to_right(
+            outer_rect,
+            self.border,
+            self.height_fraction,
+            viewed.get_health().fraction(),
+            self.position,
+        )
+    }
+}
+
+impl<E: HasHealth + HasBox> View for HealthBarView<E> {
+    type Viewed = E;
+
+    fn render(
+        &mut self,
+        viewed: &Self::Viewed,
+        _args: &RenderArgs,
+        camera: &Camera,
+        ctx: graphics::Context,
+        _texture_registry: &TextureRegistry,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        if let Some((transform, rect)) = camera.box_renderables(viewed.get_box(), ctx) {
+            graphics::rectangle(self.bg_color, self.background_rect(&rect), transform, gl);
+            graphics::rectangle(self.color, self.fill_rect(viewed, &rect), transform, gl);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gameobject::entity::Entity;
+
+    #[test]
+    fn half_health_fills_half_the_bar() {
+        let mut entity = Entity::new().unwrap();
+        let max = entity.get_health().max();
+        entity.get_health_mut().raw_damage(max / 2.0);
+
+        let view = HealthBarView::<Entity>::new(
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+            1.0,
+            InsetPosition::Inverse(0.0),
+            0.3,
+        );
+        let outer = [0.0, 0.0, 22.0, 12.0];
+
+        let background = view.background_rect(&outer);
+        let fill = view.fill_rect(&entity, &outer);
+        assert_eq!(background[2], 20.0);
+        assert_eq!(fill[2], 10.0);
+        assert_eq!(fill[..2], background[..2]);
+        assert_eq!(fill[3], background[3]);
+    }
+}
@@ -1,2 +1,8 @@
+/// Text that rises and fades out, i.e. damage numbers
+pub mod floating_text;
+/// A bar showing a game object's remaining health
+pub mod health_bar;
 /// Renders a rectangular area that fills up according to a given fraction
 pub mod progress_bar;
+/// Renders text in the world
+pub mod text;
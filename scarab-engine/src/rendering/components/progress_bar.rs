@@ -1,7 +1,16 @@
-use graphics::types::Scalar;
+use graphics::{
+    types::{Color, Scalar},
+    Context,
+};
+use opengl_graphics::GlGraphics;
 use serde::{Deserialize, Serialize};
 use shapes::Point;
 
+use crate::{error::RenderResult, rendering::Camera};
+
+/// The number of straight segments used to approximate a full circle in [arc_fill]
+const ARC_SEGMENTS: usize = 32;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 /// Determines the position of a progress bar when inset in a larger box.
 /// e.g. When making an [inset_left_to_right] `Normal` pushes the y coordinate *down* from the top border while `Inverse` pushes the y coordinate *up* from the bottom border
@@ -144,6 +153,79 @@ pub fn inset_bottom_to_top(
     )
 }
 
+/// Make a pie slice covering `fraction` of a full circle, starting at `start_angle` (in radians) and sweeping clockwise.
+/// The first vertex is the center and the rest lie along the arc, so the slice can be drawn as a triangle fan.
+/// A fraction of 0 gives no vertices
+pub fn arc_fill(
+    center: Point,
+    radius: Scalar,
+    fraction: Scalar,
+    start_angle: Scalar,
+) -> Vec<[Scalar; 2]> {
+    let fraction = fraction.clamp(0.0, 1.0);
+    if fraction == 0.0 {
+        return Vec::new();
+    }
+
+    let sweep = fraction * std::f64::consts::TAU;
+    let segments = usize::max((fraction * ARC_SEGMENTS as Scalar).ceil() as usize, 1);
+    let mut vertices = Vec::with_capacity(segments + 2);
+    vertices.push([center.x, center.y]);
+    for i in 0..=segments {
+        let angle = start_angle + sweep * (i as Scalar / segments as Scalar);
+        vertices.push([
+            center.x + radius * angle.cos(),
+            center.y + radius * angle.sin(),
+        ]);
+    }
+
+    vertices
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Renders a circular progress indicator, i.e. an ability cooldown over an entity
+pub struct RadialProgressView {
+    /// The color of the filled slice
+    pub color: Color,
+    /// The radius of the indicator in world pixels
+    pub radius: Scalar,
+    /// The angle (in radians) where the slice starts filling from
+    pub start_angle: Scalar,
+}
+
+impl RadialProgressView {
+    /// Creates a new radial progress view
+    pub fn new(color: Color, radius: Scalar, start_angle: Scalar) -> Self {
+        Self {
+            color,
+            radius,
+            start_angle,
+        }
+    }
+
+    /// Renders a slice for `fraction` of the circle centered at `center` in world coordinates
+    pub fn render_fraction(
+        &self,
+        fraction: Scalar,
+        center: Point,
+        camera: &Camera,
+        ctx: Context,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        let vertices = arc_fill(
+            [0.0, 0.0].into(),
+            self.radius * camera.points_per_pixel(),
+            fraction,
+            self.start_angle,
+        );
+        if !vertices.is_empty() {
+            graphics::polygon(self.color, &vertices, camera.transform(&ctx, center), gl);
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -541,4 +623,37 @@ mod test {
             ]
         );
     }
+
+    fn vertex_angle(center: [f64; 2], vertex: [f64; 2]) -> f64 {
+        f64::atan2(vertex[1] - center[1], vertex[0] - center[0])
+    }
+
+    #[test]
+    fn arc_fill_sweeps_the_fraction() {
+        let center = [10.0, 20.0];
+        let start = 0.5;
+        let tau = std::f64::consts::TAU;
+
+        assert!(arc_fill(center.into(), 5.0, 0.0, start).is_empty());
+
+        for fraction in [0.25, 0.5, 1.0] {
+            let vertices = arc_fill(center.into(), 5.0, fraction, start);
+            assert_eq!(vertices[0], center);
+
+            let first = vertices[1];
+            let last = *vertices.last().unwrap();
+            assert!((vertex_angle(center, first) - start).abs() < 1e-9);
+            let end = (start + fraction * tau + tau / 2.0).rem_euclid(tau) - tau / 2.0;
+            assert!((vertex_angle(center, last) - end).abs() < 1e-9);
+            for v in &vertices[1..] {
+                let dist = f64::hypot(v[0] - center[0], v[1] - center[1]);
+                assert!((dist - 5.0).abs() < 1e-9);
+            }
+        }
+
+        // A full circle ends where it started
+        let full = arc_fill(center.into(), 5.0, 1.0, start);
+        let (first, last) = (full[1], *full.last().unwrap());
+        assert!((first[0] - last[0]).abs() < 1e-9 && (first[1] - last[1]).abs() < 1e-9);
+    }
 }
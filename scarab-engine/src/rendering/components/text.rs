@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use derivative::Derivative;
+use graphics::{
+    types::{Color, Matrix2d},
+    Context,
+};
+use opengl_graphics::{GlGraphics, GlyphCache, TextureSettings};
+use serde::{Deserialize, Serialize};
+use shapes::Point;
+
+use crate::{
+    error::{RenderError, RenderResult},
+    rendering::Camera,
+};
+
+#[derive(Derivative, Serialize, Deserialize)]
+#[derivative(Debug)]
+/// Renders text at positions in the world with a font loaded from a file.
+/// The font is loaded the first time text is rendered
+///
+/// ```no_run
+/// # use graphics::Context;
+/// # use opengl_graphics::GlGraphics;
+/// # use scarab_engine::{error::RenderResult, rendering::{components::text::TextView, Camera}};
+/// fn render_score(
+///     score: u32,
+///     view: &mut TextView,
+///     camera: &Camera,
+///     ctx: Context,
+///     gl: &mut GlGraphics,
+/// ) -> RenderResult<()> {
+///     view.render_text(&format!("Score: {score}"), [10.0, 10.0].into(), camera, ctx, gl)
+/// }
+/// ```
+pub struct TextView {
+    /// The path to the font file
+    pub font_path: PathBuf,
+    /// The font size in points
+    pub size: u32,
+    /// The color of the text
+    pub color: Color,
+    #[derivative(Debug = "ignore")]
+    #[serde(skip)]
+    glyphs: Option<GlyphCache<'static>>,
+}
+
+impl TextView {
+    /// Creates a new TextView using the font at `font_path`
+    pub fn new(font_path: PathBuf, size: u32, color: Color) -> Self {
+        Self {
+            font_path,
+            size,
+            color,
+            glyphs: None,
+        }
+    }
+
+    fn glyphs(&mut self) -> RenderResult<&mut GlyphCache<'static>> {
+        if self.glyphs.is_none() {
+            let glyphs =
+                GlyphCache::new(&self.font_path, (), TextureSettings::new()).map_err(|e| {
+                    RenderError::CouldNotLoadFont(self.font_path.clone(), e.to_string())
+                })?;
+            self.glyphs = Some(glyphs);
+        }
+
+        // The cache was just loaded if it wasn't already
+        Ok(self.glyphs.as_mut().unwrap())
+    }
+
+    /// The screen transform for text with the start of its baseline at `pos` in world coordinates
+    pub fn text_transform(&self, pos: Point, camera: &Camera, ctx: &Context) -> Matrix2d {
+        camera.transform(ctx, pos)
+    }
+
+    /// Renders the text with the start of its baseline at `pos` in world coordinates
+    pub fn render_text(
+        &mut self,
+        text: &str,
+        pos: Point,
+        camera: &Camera,
+        ctx: Context,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        self.render_text_colored(text, self.color, pos, camera, ctx, gl)
+    }
+
+    /// Renders the text in the given color instead of the view's color.
+    /// The start of its baseline is at `pos` in world coordinates
+    pub fn render_text_colored(
+        &mut self,
+        text: &str,
+        color: Color,
+        pos: Point,
+        camera: &Camera,
+        ctx: Context,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        let size = self.size;
+        let transform = self.text_transform(pos, camera, &ctx);
+        graphics::text(color, size, text, self.glyphs()?, transform, gl)
+            .map_err(RenderError::TextRendering)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use graphics::Transformed;
+
+    use crate::types::physbox::PhysBox;
+
+    #[test]
+    fn text_transform_maps_world_to_screen() {
+        let view = TextView::new("font.ttf".into(), 12, [1.0, 1.0, 1.0, 1.0]);
+        // 4 screen points per world pixel with a 50 point bar above and below
+        let camera = Camera::new(
+            PhysBox::new([10.0, 20.0, 100.0, 50.0]).unwrap(),
+            [400.0, 300.0],
+        );
+        let ctx = Context::new();
+
+        let transform = view.text_transform([15.0, 30.0].into(), &camera, &ctx);
+        assert_eq!(transform, ctx.transform.trans(20.0, 90.0));
+    }
+}
@@ -5,11 +5,15 @@ use graphics::{
 };
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use shapes::Point;
 
 use self::registry::TextureRegistry;
-use crate::{error::RenderResult, types::physbox::PhysBox};
+use crate::{
+    error::RenderResult,
+    types::{physbox::PhysBox, Velocity},
+};
 
 #[cfg(feature = "component-rendering")]
 /// For rendering generic reusable components
@@ -17,6 +21,8 @@ pub mod components;
 #[cfg(feature = "debug-rendering")]
 /// Rendering additional debugging info
 pub mod debug;
+/// Backgrounds that scroll at a different rate than the camera
+pub mod parallax;
 /// Rendering registries
 pub mod registry;
 /// Specifically for rendering sprites
@@ -39,7 +45,36 @@ pub trait View {
     ) -> RenderResult<()>;
 }
 
+/// A view whose concrete type has been erased, so views of different types can be kept together
+pub type BoxedView<T> = Box<dyn View<Viewed = T>>;
+
+impl<V: View + ?Sized> View for Box<V> {
+    type Viewed = V::Viewed;
+
+    fn render(
+        &mut self,
+        viewed: &Self::Viewed,
+        args: &RenderArgs,
+        camera: &Camera,
+        ctx: Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        (**self).render(viewed, args, camera, ctx, texture_registry, gl)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// How many entities were drawn by a single render, based on whether their boxes were on the camera
+pub struct RenderStats {
+    /// The number of entities that were on the camera
+    pub drawn: usize,
+    /// The number of entities that were skipped for being off of the camera
+    pub culled: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "CameraData")]
 /// Controls how the pixel art is rendered and maintained through play
 ///
 /// A point is usually what is thought of as a pixel on the monitor
@@ -47,15 +82,61 @@ pub trait View {
 /// A pixel is one single square in the pixel art graphics, and is also the unit size for physics operations
 pub struct Camera {
     /// The number of "points" per "pixel", see struct level documentation for more
+    #[serde(skip)]
     points_per_pixel: f64,
     /// The camera's position and dimensions in world coordinates
     physbox: PhysBox,
     /// The window's size in points
     window_size: [f64; 2],
     /// The width of each vertical bar necessary to fill up the window (in points)
+    #[serde(skip)]
     vertical_bar_width: f64,
     /// The width of each horizontal bar necessary to fill up the window (in points)
+    #[serde(skip)]
     horizontal_bar_height: f64,
+    /// How far ahead of a followed target the camera looks, in seconds of the target's velocity
+    lookahead: f64,
+    /// The gameplay zoom, multiplies the points per pixel
+    zoom: f64,
+    /// The strongest distance (in pixels) the view can be shaken from the camera's position
+    #[serde(skip)]
+    shake_magnitude: f64,
+    /// The total length of the current shake in seconds
+    #[serde(skip)]
+    shake_duration: f64,
+    /// The time left in the current shake in seconds
+    #[serde(skip)]
+    shake_remaining: f64,
+    /// How far the view is currently shaken from the camera's position (in pixels)
+    #[serde(skip)]
+    shake_offset: [f64; 2],
+}
+
+/// The smallest zoom a camera can have, to keep it from inverting or dividing by 0
+const MIN_ZOOM: f64 = 0.01;
+
+fn default_zoom() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+/// The serialized fields of a [Camera], the rest are derived from these when it's loaded
+struct CameraData {
+    physbox: PhysBox,
+    window_size: [f64; 2],
+    #[serde(default)]
+    lookahead: f64,
+    #[serde(default = "default_zoom")]
+    zoom: f64,
+}
+
+impl From<CameraData> for Camera {
+    fn from(data: CameraData) -> Self {
+        let mut camera = Self::new(data.physbox, data.window_size);
+        camera.set_lookahead(data.lookahead);
+        camera.set_zoom(data.zoom);
+        camera
+    }
 }
 
 impl Camera {
@@ -67,6 +148,12 @@ impl Camera {
             window_size: window_size.clone(),
             vertical_bar_width: 0.0,
             horizontal_bar_height: 0.0,
+            lookahead: 0.0,
+            zoom: 1.0,
+            shake_magnitude: 0.0,
+            shake_duration: 0.0,
+            shake_remaining: 0.0,
+            shake_offset: [0.0, 0.0],
         };
 
         s.set_window_size(&window_size);
@@ -92,18 +179,139 @@ impl Camera {
         self.horizontal_bar_height = (h_w - self.physbox.size().h * self.points_per_pixel) / 2.0;
     }
 
+    /// Sets the gameplay zoom, where 2.0 shows half as much of the world in each direction.
+    /// The zoom is kept above a small positive minimum
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.zoom = zoom.max(MIN_ZOOM);
+    }
+
+    /// The camera's gameplay zoom
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// The part of the world that's actually shown, which is the camera's physbox
+    /// shrunk around its center by the [zoom](Camera::set_zoom)
+    pub fn visible_box(&self) -> PhysBox {
+        let size = *self.physbox.size();
+        let shrink = (1.0 - 1.0 / self.zoom) / 2.0;
+        let mut visible = self.physbox;
+        visible.set_pos(*self.physbox.pos() + [size.w * shrink, size.h * shrink]);
+        // Can't fail because zoom is always positive
+        let _ = visible.set_size([size.w / self.zoom, size.h / self.zoom].into());
+        visible
+    }
+
+    /// Shakes the view by up to `magnitude` pixels, fading out over `duration` seconds.
+    /// Only the rendering is shaken, the camera's [physbox](Camera::physbox) doesn't move.
+    /// Shakes that overlap keep the larger magnitude and the longer time remaining
+    pub fn add_shake(&mut self, magnitude: f64, duration: f64) {
+        if duration <= 0.0 {
+            return;
+        }
+        self.shake_magnitude = f64::max(self.current_shake(), magnitude);
+        self.shake_remaining = f64::max(self.shake_remaining, duration);
+        self.shake_duration = self.shake_remaining;
+    }
+
+    /// How far the view can currently be shaken, which falls off linearly over the shake's duration
+    pub fn current_shake(&self) -> f64 {
+        if self.shake_duration > 0.0 {
+            self.shake_magnitude * self.shake_remaining / self.shake_duration
+        } else {
+            0.0
+        }
+    }
+
+    /// Advances the camera's shake by `dt` seconds, picking a new random offset for the view from `rng`
+    /// (i.e. [Scene::rng_mut](crate::scene::Scene::rng_mut) so that seeded scenes shake the same way)
+    pub fn update(&mut self, dt: f64, rng: &mut impl Rng) {
+        self.shake_remaining = f64::max(self.shake_remaining - dt, 0.0);
+        let magnitude = self.current_shake();
+        self.shake_offset = if magnitude > 0.0 {
+            [
+                rng.gen_range(-magnitude..=magnitude),
+                rng.gen_range(-magnitude..=magnitude),
+            ]
+        } else {
+            [0.0, 0.0]
+        };
+    }
+
+    /// Sets how far ahead of a moving target [Camera::follow] looks.
+    /// The camera centers on the target's center offset by `target_velocity * factor`
+    pub fn set_lookahead(&mut self, factor: f64) {
+        self.lookahead = factor;
+    }
+
+    /// Moves the camera toward centering on `target` (plus any [lookahead](Camera::set_lookahead)).
+    /// `lerp` is the fraction of the remaining distance to move this call, so values
+    /// less than 1 smooth the camera's movement.
+    /// When `bounds` are given, the camera is kept from showing anything outside of them
+    pub fn follow(
+        &mut self,
+        target: &PhysBox,
+        target_velocity: Velocity,
+        lerp: f64,
+        bounds: Option<&PhysBox>,
+    ) {
+        let size = *self.physbox.size();
+        let goal_center = target.center() + target_velocity * self.lookahead;
+        let mut goal = Point {
+            x: goal_center.x - size.w / 2.0,
+            y: goal_center.y - size.h / 2.0,
+        };
+
+        if let Some(bounds) = bounds {
+            goal.x = Self::clamp_to_bounds(goal.x, size.w, bounds.left_x(), bounds.right_x());
+            goal.y = Self::clamp_to_bounds(goal.y, size.h, bounds.top_y(), bounds.bottom_y());
+        }
+
+        let lerp = lerp.clamp(0.0, 1.0);
+        let pos = *self.physbox.pos();
+        self.physbox.set_pos(Point {
+            x: pos.x + (goal.x - pos.x) * lerp,
+            y: pos.y + (goal.y - pos.y) * lerp,
+        });
+    }
+
+    /// Clamps the near side of a camera span of `length` to within `min..max`,
+    /// centering it when the span doesn't fit
+    fn clamp_to_bounds(near: f64, length: f64, min: f64, max: f64) -> f64 {
+        if max - min < length {
+            (min + max - length) / 2.0
+        } else {
+            near.clamp(min, max - length)
+        }
+    }
+
+    /// The camera's position and dimensions in world coordinates
+    pub fn physbox(&self) -> &PhysBox {
+        &self.physbox
+    }
+
     /// Creates a trasnform matrix for the given point from world coordinates to screen coordinates
     pub fn transform(&self, ctx: &Context, pos: Point) -> [[f64; 3]; 2] {
-        let top_left = pos - *self.physbox.pos();
-        let top_left_scaled = top_left * self.points_per_pixel.into()
+        let top_left = pos - *self.visible_box().pos() + self.shake_offset;
+        let top_left_scaled = top_left * self.points_per_pixel().into()
             + [self.vertical_bar_width, self.horizontal_bar_height];
 
         ctx.transform.trans(top_left_scaled.x, top_left_scaled.y)
     }
 
-    /// The actual screen "points" per pixel-art-pixel for the camera
+    /// Maps a point on the screen (i.e. the mouse cursor) back to world coordinates.
+    /// This is the inverse of [Camera::transform], so points in the gutters map to
+    /// world coordinates outside of the camera's physbox, and while the camera is shaking
+    /// the point is whatever is drawn under it
+    pub fn screen_to_world(&self, screen_point: Point) -> Point {
+        let unscaled = (screen_point - [self.vertical_bar_width, self.horizontal_bar_height])
+            / self.points_per_pixel().into();
+        unscaled + *self.visible_box().pos() - self.shake_offset
+    }
+
+    /// The actual screen "points" per pixel-art-pixel for the camera, including its zoom
     pub fn points_per_pixel(&self) -> f64 {
-        self.points_per_pixel
+        self.points_per_pixel * self.zoom
     }
 
     /// Gives the simple transform and redering rectangle for a 2D PhysBox
@@ -112,14 +320,14 @@ impl Camera {
         physbox: &PhysBox,
         ctx: Context,
     ) -> Option<([[f64; 3]; 2], [f64; 4])> {
-        if physbox.has_overlap(&self.physbox) {
+        if physbox.has_overlap(&self.visible_box()) {
             let transform = self.transform(&ctx, *physbox.pos());
             let [x1, y1]: [Scalar; 2] = (*physbox.size()).into();
             let rect = graphics::rectangle::rectangle_by_corners(
                 0.0,
                 0.0,
-                x1 * self.points_per_pixel as f64,
-                y1 * self.points_per_pixel as f64,
+                x1 * self.points_per_pixel(),
+                y1 * self.points_per_pixel(),
             );
 
             Some((transform, rect))
@@ -137,42 +345,397 @@ impl Camera {
         ctx: Context,
         gl: &mut GlGraphics,
     ) {
-        graphics::rectangle(
-            color,
-            [0.0, 0.0, self.vertical_bar_width, self.window_size[1]],
-            ctx.transform,
-            gl,
+        for rect in self.gutter_rects() {
+            graphics::rectangle(color, rect, ctx.transform, gl);
+        }
+    }
+
+    /// The left, right, top and bottom gutters' screen rectangles.
+    /// Their inner edges move with the shake, so they cover the edges of the shaken view
+    /// and any gap it leaves while still reaching the edges of the window
+    fn gutter_rects(&self) -> [[f64; 4]; 4] {
+        let [width, height] = self.window_size;
+        let [dx, dy] = [
+            self.shake_offset[0] * self.points_per_pixel(),
+            self.shake_offset[1] * self.points_per_pixel(),
+        ];
+        let left = f64::max(self.vertical_bar_width + dx, 0.0);
+        let right = f64::min(width - self.vertical_bar_width + dx, width);
+        let top = f64::max(self.horizontal_bar_height + dy, 0.0);
+        let bottom = f64::min(height - self.horizontal_bar_height + dy, height);
+
+        [
+            [0.0, 0.0, left, height],
+            [right, 0.0, width - right, height],
+            [0.0, 0.0, width, top],
+            [0.0, bottom, width, height - bottom],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use opengl_graphics::{Filter, OpenGL};
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        gameobject::entity::Entity,
+        scene::SceneRng,
+        test_utils::{create_test_assets, gl_window},
+    };
+
+    /// Counts how many times it's been rendered
+    struct RecordingView(Rc<Cell<usize>>);
+
+    impl View for RecordingView {
+        type Viewed = Entity;
+
+        fn render(
+            &mut self,
+            _viewed: &Self::Viewed,
+            _args: &RenderArgs,
+            _camera: &Camera,
+            _ctx: Context,
+            _texture_registry: &TextureRegistry,
+            _gl: &mut GlGraphics,
+        ) -> RenderResult<()> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn boxed_views_are_views() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("boxed-views");
+        let texture_registry =
+            TextureRegistry::new(assets_path, "default.png".into(), &[], Filter::Nearest).unwrap();
+        let mut gl = GlGraphics::new(OpenGL::V3_2);
+        let camera = Camera::new(PhysBox::new([0.0, 0.0, 64.0, 64.0]).unwrap(), [64.0, 64.0]);
+        let args = RenderArgs {
+            ext_dt: 0.0,
+            window_size: [64.0, 64.0],
+            draw_size: [64, 64],
+        };
+        let entity = Entity::new().unwrap();
+
+        /// Stands in for the places generic over a view, i.e. [crate::scene::Scene]
+        fn render_all<V: View<Viewed = Entity>>(
+            views: &mut [V],
+            entity: &Entity,
+            args: &RenderArgs,
+            camera: &Camera,
+            texture_registry: &TextureRegistry,
+            gl: &mut GlGraphics,
+        ) -> RenderResult<()> {
+            for view in views {
+                view.render(entity, args, camera, Context::new(), texture_registry, gl)?;
+            }
+            Ok(())
+        }
+
+        let first = Rc::new(Cell::new(0));
+        let second = Rc::new(Cell::new(0));
+        let mut views: Vec<BoxedView<Entity>> = vec![
+            Box::new(RecordingView(first.clone())),
+            Box::new(RecordingView(second.clone())),
+        ];
+        render_all(
+            &mut views,
+            &entity,
+            &args,
+            &camera,
+            &texture_registry,
+            &mut gl,
+        )
+        .unwrap();
+        assert_eq!((first.get(), second.get()), (1, 1));
+
+        // the forwarding impl goes through to the erased view
+        let view: &mut dyn View<Viewed = Entity> = &mut views[1];
+        view.render(
+            &entity,
+            &args,
+            &camera,
+            Context::new(),
+            &texture_registry,
+            &mut gl,
+        )
+        .unwrap();
+        assert_eq!((first.get(), second.get()), (1, 2));
+    }
+
+    #[test]
+    fn deserialized_camera_recomputes_derived_fields() {
+        let camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [400.0, 400.0],
         );
+        assert_eq!(camera.points_per_pixel, 4.0);
+        assert_eq!(camera.vertical_bar_width, 0.0);
+        assert_eq!(camera.horizontal_bar_height, 100.0);
 
-        graphics::rectangle(
-            color,
-            [
-                self.window_size[0] - self.vertical_bar_width,
-                0.0,
-                self.vertical_bar_width,
-                self.window_size[1],
-            ],
-            ctx.transform,
-            gl,
+        let serialized = serde_json::to_string(&camera).unwrap();
+        assert!(!serialized.contains("points_per_pixel"));
+
+        let loaded: Camera = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(loaded.physbox, camera.physbox);
+        assert_eq!(loaded.window_size, camera.window_size);
+        assert_eq!(loaded.points_per_pixel, camera.points_per_pixel);
+        assert_eq!(loaded.vertical_bar_width, camera.vertical_bar_width);
+        assert_eq!(loaded.horizontal_bar_height, camera.horizontal_bar_height);
+    }
+
+    #[test]
+    fn shake_decays_over_duration() {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
+        );
+        let physbox = *camera.physbox();
+        camera.add_shake(4.0, 1.0);
+        assert_eq!(camera.current_shake(), 4.0);
+
+        let mut rng = rand::thread_rng();
+        for (dt, expected) in [(0.25, 3.0), (0.25, 2.0), (0.25, 1.0), (0.5, 0.0)] {
+            camera.update(dt, &mut rng);
+            assert_eq!(camera.current_shake(), expected);
+            assert!(camera.shake_offset.iter().all(|o| o.abs() <= expected));
+            assert_eq!(*camera.physbox(), physbox);
+        }
+        assert_eq!(camera.shake_offset, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn no_shake_leaves_transform_unchanged() {
+        let ctx = Context::new();
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
         );
+        let pos: Point = [10.0, 5.0].into();
+        let before = camera.transform(&ctx, pos);
 
-        graphics::rectangle(
-            color,
-            [0.0, 0.0, self.window_size[0], self.horizontal_bar_height],
-            ctx.transform,
-            gl,
+        camera.update(0.1, &mut rand::thread_rng());
+        assert_eq!(camera.transform(&ctx, pos), before);
+        assert_eq!(before, ctx.transform.trans(100.0, 50.0));
+    }
+
+    #[test]
+    fn follow_looks_ahead_of_moving_target() {
+        let target = PhysBox::new([50.0, 50.0, 10.0, 10.0]).unwrap();
+        let velocity: Velocity = [20.0, 0.0].into();
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
+        );
+
+        camera.follow(&target, velocity, 1.0, None);
+        let centered = camera.physbox().center();
+        assert_eq!(centered, target.center());
+
+        camera.set_lookahead(0.5);
+        camera.follow(&target, velocity, 1.0, None);
+        let ahead = camera.physbox().center();
+        assert_eq!(ahead.x, centered.x + 10.0);
+        assert_eq!(ahead.y, centered.y);
+    }
+
+    #[test]
+    fn follow_lookahead_stays_in_bounds() {
+        let bounds = PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap();
+        let target = PhysBox::new([75.0, 50.0, 10.0, 10.0]).unwrap();
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
         );
+        camera.set_lookahead(1.0);
 
-        graphics::rectangle(
-            color,
+        camera.follow(&target, [50.0, 0.0].into(), 1.0, Some(&bounds));
+        assert_eq!(camera.physbox().right_x(), bounds.right_x());
+    }
+
+    #[test]
+    fn follow_lerp_converges_on_target() {
+        let target = PhysBox::new([100.0, 80.0, 10.0, 10.0]).unwrap();
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
+        );
+
+        let distance = |camera: &Camera| {
+            let offset = camera.physbox().center() - target.center();
+            f64::hypot(offset.x, offset.y)
+        };
+        let mut last_distance = distance(&camera);
+        for _ in 0..50 {
+            camera.follow(&target, [0.0, 0.0].into(), 0.25, None);
+            let new_distance = distance(&camera);
+            assert!(new_distance < last_distance);
+            last_distance = new_distance;
+        }
+        assert!(last_distance < 0.001);
+    }
+
+    #[test]
+    fn follow_keeps_camera_inside_bounds() {
+        let bounds = PhysBox::new([0.0, 0.0, 200.0, 100.0]).unwrap();
+        let mut camera = Camera::new(
+            PhysBox::new([50.0, 50.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
+        );
+
+        for target_pos in [[-50.0, -50.0], [500.0, 20.0], [100.0, 500.0]] {
+            let target = PhysBox::new([target_pos[0], target_pos[1], 10.0, 10.0]).unwrap();
+            camera.follow(&target, [0.0, 0.0].into(), 1.0, Some(&bounds));
+            assert!(camera.physbox().is_fully_contained_by(&bounds));
+        }
+        assert_eq!(camera.physbox().bottom_y(), bounds.bottom_y());
+    }
+
+    #[test]
+    fn screen_to_world_inverts_transform() {
+        let camera = Camera::new(
+            PhysBox::new([30.0, -20.0, 100.0, 50.0]).unwrap(),
+            [500.0, 400.0],
+        );
+        let ctx = Context::new();
+
+        for world in [[30.0, -20.0], [0.0, 0.0], [75.5, 12.25], [130.0, 30.0]] {
+            let world: Point = world.into();
+            let transform = camera.transform(&ctx, world);
+            let screen: Point = [transform[0][2], transform[1][2]].into();
+            assert_eq!(camera.screen_to_world(screen), world);
+        }
+    }
+
+    #[test]
+    fn screen_to_world_inverts_shaken_transform() {
+        let mut camera = Camera::new(
+            PhysBox::new([30.0, -20.0, 100.0, 50.0]).unwrap(),
+            [500.0, 400.0],
+        );
+        camera.add_shake(4.0, 1.0);
+        camera.update(0.1, &mut SceneRng::seed_from_u64(7));
+        assert_ne!(camera.shake_offset, [0.0, 0.0]);
+        let ctx = Context::new();
+
+        for world in [[30.0, -20.0], [0.0, 0.0], [75.5, 12.25]] {
+            let world: Point = world.into();
+            let transform = camera.transform(&ctx, world);
+            let screen: Point = [transform[0][2], transform[1][2]].into();
+            let back = camera.screen_to_world(screen);
+            assert!((back.x - world.x).abs() < 1e-9 && (back.y - world.y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn shake_is_reproducible_from_seeded_rng() {
+        let mut a = Camera::new(
+            PhysBox::new([0.0, 0.0, 40.0, 30.0]).unwrap(),
+            [400.0, 300.0],
+        );
+        let mut b = a.clone();
+        a.add_shake(4.0, 1.0);
+        b.add_shake(4.0, 1.0);
+
+        let (mut rng_a, mut rng_b) = (SceneRng::seed_from_u64(3), SceneRng::seed_from_u64(3));
+        for _ in 0..5 {
+            a.update(0.1, &mut rng_a);
+            b.update(0.1, &mut rng_b);
+            assert_eq!(a.shake_offset, b.shake_offset);
+        }
+    }
+
+    #[test]
+    fn gutters_follow_shake_to_window_edges() {
+        // Letterboxed with 50 point bars above and below
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [400.0, 300.0],
+        );
+        assert_eq!(
+            camera.gutter_rects(),
             [
-                0.0,
-                self.window_size[1] - self.horizontal_bar_height,
-                self.window_size[0],
-                self.horizontal_bar_height,
-            ],
-            ctx.transform,
-            gl,
+                [0.0, 0.0, 0.0, 300.0],
+                [400.0, 0.0, 0.0, 300.0],
+                [0.0, 0.0, 400.0, 50.0],
+                [0.0, 250.0, 400.0, 50.0],
+            ]
         );
+
+        // Shaken 2 pixels (8 points) right and 1 pixel (4 points) up
+        camera.shake_offset = [2.0, -1.0];
+        assert_eq!(
+            camera.gutter_rects(),
+            [
+                [0.0, 0.0, 8.0, 300.0],
+                [400.0, 0.0, 0.0, 300.0],
+                [0.0, 0.0, 400.0, 46.0],
+                [0.0, 246.0, 400.0, 54.0],
+            ]
+        );
+    }
+
+    #[test]
+    fn screen_to_world_in_gutter_is_outside_camera() {
+        // Letterboxed with bars above and below
+        let camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [400.0, 400.0],
+        );
+
+        let world = camera.screen_to_world([0.0, 0.0].into());
+        assert_eq!(world.x, 0.0);
+        assert!(world.y < 0.0);
+        assert!(!camera.physbox().contains_pos(world));
+    }
+
+    #[test]
+    fn zooming_in_halves_visible_extent() {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [400.0, 400.0],
+        );
+        assert_eq!(camera.visible_box(), *camera.physbox());
+
+        camera.set_zoom(2.0);
+        assert_eq!(
+            camera.visible_box(),
+            PhysBox::new([25.0, 12.5, 50.0, 25.0]).unwrap()
+        );
+        assert_eq!(camera.points_per_pixel(), 8.0);
+        // The visible part of the world still fills the same part of the screen
+        assert_eq!(
+            camera.screen_to_world([0.0, 100.0].into()),
+            *camera.visible_box().pos()
+        );
+
+        camera.set_zoom(-1.0);
+        assert_eq!(camera.zoom(), MIN_ZOOM);
+    }
+
+    #[test]
+    fn zoomed_box_renderables_culls_outside_visible_box() {
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 50.0]).unwrap(),
+            [400.0, 400.0],
+        );
+        let ctx = Context::new();
+        let near_corner = PhysBox::new([5.0, 5.0, 10.0, 10.0]).unwrap();
+        let middle = PhysBox::new([45.0, 20.0, 10.0, 10.0]).unwrap();
+
+        assert!(camera.box_renderables(&near_corner, ctx).is_some());
+        camera.set_zoom(2.0);
+        assert!(camera.box_renderables(&near_corner, ctx).is_none());
+
+        let (transform, rect) = camera.box_renderables(&middle, ctx).unwrap();
+        assert_eq!(rect, [0.0, 0.0, 80.0, 80.0]);
+        assert_eq!(transform[0][2], (45.0 - 25.0) * 8.0);
+        assert_eq!(transform[1][2], 100.0 + (20.0 - 12.5) * 8.0);
     }
 }
@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use graphics::{types::Scalar, Image, ImageSize, Transformed};
+use opengl_graphics::GlGraphics;
+use piston::RenderArgs;
+use serde::{Deserialize, Serialize};
+use shapes::{Point, Size};
+
+use super::{registry::TextureRegistry, Camera, View};
+use crate::error::RenderResult;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A background texture that scrolls slower (or faster) than the camera, tiled to fill the camera's view.
+///
+/// A `scroll_factor` of 0 keeps the texture fixed to the screen, and a factor of 1 locks it to
+/// the world like any other game object. Anything in between appears farther away than the field
+pub struct ParallaxLayer {
+    /// The texture to tile across the view
+    pub texture_path: PathBuf,
+    /// How far the texture moves for each unit the camera moves, on each axis
+    pub scroll_factor: [Scalar; 2],
+}
+
+impl ParallaxLayer {
+    /// Creates a layer tiling the texture at `texture_path`, scrolling by `scroll_factor`
+    pub fn new(texture_path: PathBuf, scroll_factor: [Scalar; 2]) -> Self {
+        Self {
+            texture_path,
+            scroll_factor,
+        }
+    }
+
+    /// Where the texture is drawn relative to the top left corner of the camera's view,
+    /// before it's tiled, when the camera is at `camera_pos`
+    pub fn texture_offset(&self, camera_pos: Point) -> Point {
+        [
+            -camera_pos.x * self.scroll_factor[0],
+            -camera_pos.y * self.scroll_factor[1],
+        ]
+        .into()
+    }
+
+    /// The top left corner of each tile of a `texture_size` texture that's needed to cover a view
+    /// of `view_size`, relative to the view's top left corner
+    fn tile_origins(&self, camera_pos: Point, view_size: Size, texture_size: Size) -> Vec<Point> {
+        let offset = self.texture_offset(camera_pos);
+        // Start from the tile that covers the view's top left corner
+        let start_x = -(-offset.x).rem_euclid(texture_size.w);
+        let start_y = -(-offset.y).rem_euclid(texture_size.h);
+
+        let mut origins = Vec::new();
+        let mut y = start_y;
+        while y < view_size.h {
+            let mut x = start_x;
+            while x < view_size.w {
+                origins.push([x, y].into());
+                x += texture_size.w;
+            }
+            y += texture_size.h;
+        }
+        origins
+    }
+}
+
+impl View for ParallaxLayer {
+    type Viewed = ();
+
+    fn render(
+        &mut self,
+        _viewed: &Self::Viewed,
+        _args: &RenderArgs,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        let texture = texture_registry.get_or_default(&self.texture_path);
+        let (w, h) = texture.get_size();
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+
+        let visible = camera.visible_box();
+        let scale_factor = camera.points_per_pixel();
+        let image = Image::new();
+        for origin in self.tile_origins(
+            *visible.pos(),
+            *visible.size(),
+            [w as Scalar, h as Scalar].into(),
+        ) {
+            let transform = camera
+                .transform(&ctx, *visible.pos() + origin)
+                .scale(scale_factor, scale_factor);
+            image.draw(texture, &ctx.draw_state, transform, gl);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn texture_offset_scales_camera_position() {
+        let camera_pos: Point = [100.0, 40.0].into();
+
+        let fixed = ParallaxLayer::new("bg.png".into(), [0.0, 0.0]);
+        assert_eq!(fixed.texture_offset(camera_pos), [0.0, 0.0].into());
+
+        let world_locked = ParallaxLayer::new("bg.png".into(), [1.0, 1.0]);
+        assert_eq!(
+            world_locked.texture_offset(camera_pos),
+            [-100.0, -40.0].into()
+        );
+
+        let distant = ParallaxLayer::new("bg.png".into(), [0.5, 0.25]);
+        assert_eq!(distant.texture_offset(camera_pos), [-50.0, -10.0].into());
+    }
+
+    #[test]
+    fn tiles_cover_the_view() {
+        let layer = ParallaxLayer::new("bg.png".into(), [0.5, 0.0]);
+        let origins =
+            layer.tile_origins([70.0, 0.0].into(), [50.0, 30.0].into(), [20.0, 20.0].into());
+
+        // Offset by -35, so the first column starts 15 units to the left of the view
+        let expected: Vec<Point> = [
+            [-15.0, 0.0],
+            [5.0, 0.0],
+            [25.0, 0.0],
+            [45.0, 0.0],
+            [-15.0, 20.0],
+            [5.0, 20.0],
+            [25.0, 20.0],
+            [45.0, 20.0],
+        ]
+        .into_iter()
+        .map(Point::from)
+        .collect();
+        assert_eq!(origins, expected);
+    }
+}
@@ -5,6 +5,7 @@ use derivative::Derivative;
 use opengl_graphics::{Filter, Texture, TextureSettings};
 use serde::{Deserialize, Serialize};
 
+use super::sprite::sprite_serde::TextureSettingsDef;
 use crate::{
     error::{RenderError, RenderResult},
     ScarabError, ScarabResult,
@@ -46,12 +47,17 @@ pub struct TextureRegistry {
     default_path_texture: PathTexture,
     #[derivative(Debug = "ignore")]
     textures: HashMap<PathBuf, Texture>,
+    #[derivative(Debug = "ignore")]
+    default_settings: TextureSettings,
+    /// Settings for textures that weren't loaded with the default settings
+    #[derivative(Debug = "ignore")]
+    settings: HashMap<PathBuf, TextureSettings>,
 }
 
 impl TextureRegistry {
     /// Creates a new `TextureRegistry` given the default texture path and a list of other textures.
     /// Pre-loads all texture paths given.
-    /// Textures default with "Nearest" filtering
+    /// Textures default to the given filtering unless loaded with `load_with_settings`
     /// The assets_path should be a path to the binary's assets folder
     /// This can be an absolute or relative path.
     /// If a relative path is given, it's assumed to be the path from the binary executable to the assets folder.
@@ -59,13 +65,31 @@ impl TextureRegistry {
         assets_path: PathBuf,
         default_path: PathBuf,
         other_texture_paths: &[PathBuf],
+        default_filter: Filter,
     ) -> ScarabResult<Self> {
-        let default_texture = Self::load_inner(&assets_path.join(&default_path))?;
+        Self::with_settings(
+            assets_path,
+            default_path,
+            other_texture_paths,
+            TextureSettings::new().filter(default_filter),
+        )
+    }
+
+    /// Creates a new `TextureRegistry` where textures default to the given settings rather than just a filter.
+    /// See [TextureRegistry::new]
+    pub fn with_settings(
+        assets_path: PathBuf,
+        default_path: PathBuf,
+        other_texture_paths: &[PathBuf],
+        default_settings: TextureSettings,
+    ) -> ScarabResult<Self> {
+        let default_texture =
+            Self::load_inner(&assets_path.join(&default_path), &default_settings)?;
         let default_path_texture = PathTexture::new(default_texture, default_path);
         let mut textures = HashMap::new();
 
         for path in other_texture_paths {
-            let texture = Self::load_inner(&assets_path.join(&path))?;
+            let texture = Self::load_inner(&assets_path.join(&path), &default_settings)?;
             textures.insert(path.to_path_buf(), texture);
         }
 
@@ -73,9 +97,26 @@ impl TextureRegistry {
             assets_path,
             default_path_texture,
             textures,
+            default_settings,
+            settings: HashMap::new(),
         })
     }
 
+    /// Creates a new `TextureRegistry` where textures default with "Nearest" filtering,
+    /// which keeps pixel art crisp. See [TextureRegistry::new]
+    pub fn new_nearest(
+        assets_path: PathBuf,
+        default_path: PathBuf,
+        other_texture_paths: &[PathBuf],
+    ) -> ScarabResult<Self> {
+        Self::new(
+            assets_path,
+            default_path,
+            other_texture_paths,
+            Filter::Nearest,
+        )
+    }
+
     /// Gets a loaded texture a the given path or the default texture
     pub fn get_or_default(&self, path: &PathBuf) -> &Texture {
         self.textures
@@ -97,13 +138,72 @@ impl TextureRegistry {
     /// TODO: optionally deserialize texture settings
     /// Returns the previously loaded texture for the path if it exists
     pub fn load(&mut self, path: PathBuf) -> RenderResult<Option<Texture>> {
-        let texture = Self::load_inner(&self.assets_path.join(&path))?;
+        let texture = Self::load_inner(&self.assets_path.join(&path), &self.default_settings)?;
+        self.settings.remove(&path);
         Ok(self.textures.insert(path, texture))
     }
 
-    fn load_inner(path: &PathBuf) -> RenderResult<Texture> {
-        let settings = TextureSettings::new().filter(Filter::Nearest);
-        Texture::from_path(path, &settings)
+    /// Loads the texture at the path with the given settings, i.e. to use "Linear" filtering
+    /// for a smooth background in a registry that defaults to "Nearest".
+    /// The settings are kept for when the texture is reloaded
+    /// Returns the previously loaded texture for the path if it exists
+    pub fn load_with_settings(
+        &mut self,
+        path: PathBuf,
+        settings: TextureSettings,
+    ) -> RenderResult<Option<Texture>> {
+        let texture = Self::load_inner(&self.assets_path.join(&path), &settings)?;
+        self.settings.insert(path.clone(), settings);
+        Ok(self.textures.insert(path, texture))
+    }
+
+    /// Removes a loaded texture, freeing it once the returned texture is dropped.
+    /// The default texture can't be unloaded, so unloading its path does nothing and returns `None`
+    pub fn unload(&mut self, path: &PathBuf) -> Option<Texture> {
+        if path == self.default_path_texture.path() {
+            return None;
+        }
+        self.settings.remove(path);
+        self.textures.remove(path)
+    }
+
+    /// The paths of all currently loaded textures, starting with the default texture
+    pub fn loaded_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(self.default_path_texture.path()).chain(self.textures.keys())
+    }
+
+    /// Re-loads an already loaded texture from disk, i.e. after its file has been edited.
+    /// Fails if the texture at the path was never loaded
+    pub fn reload(&mut self, path: &PathBuf) -> RenderResult<()> {
+        if path == self.default_path_texture.path() {
+            self.default_path_texture.texture =
+                Self::load_inner(&self.assets_path.join(path), &self.default_settings)?;
+        } else if self.textures.contains_key(path) {
+            let settings = self.settings.get(path).unwrap_or(&self.default_settings);
+            let texture = Self::load_inner(&self.assets_path.join(path), settings)?;
+            self.textures.insert(path.clone(), texture);
+        } else {
+            return Err(RenderError::TextureNotLoaded(path.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Re-loads every texture (including the default) from disk
+    pub fn reload_all(&mut self) -> RenderResult<()> {
+        let default_path = self.default_path_texture.path().clone();
+        self.reload(&default_path)?;
+
+        let paths: Vec<PathBuf> = self.textures.keys().cloned().collect();
+        for path in paths {
+            self.reload(&path)?;
+        }
+
+        Ok(())
+    }
+
+    fn load_inner(path: &PathBuf, settings: &TextureSettings) -> RenderResult<Texture> {
+        Texture::from_path(path, settings)
             .or_else(|e| Err(RenderError::CouldNotLoadTexture(path.clone(), e)))
     }
 }
@@ -111,29 +211,49 @@ impl TextureRegistry {
 impl TryFrom<TextureList> for TextureRegistry {
     type Error = ScarabError;
     fn try_from(value: TextureList) -> ScarabResult<Self> {
-        Self::new(
+        let mut registry = Self::with_settings(
             value.assets_path,
             value.default_texture_path,
             &value.other_texture_paths,
-        )
+            value.default_settings,
+        )?;
+        for (path, settings) in value.settings {
+            registry.load_with_settings(path, settings.0)?;
+        }
+        Ok(registry)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-/// A list of texture paths loaded in a `TextureRegistry`
+fn nearest_settings() -> TextureSettings {
+    TextureSettings::new().filter(Filter::Nearest)
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Serializes a texture's settings, for when they're the value in a map
+struct SavedTextureSettings(#[serde(with = "TextureSettingsDef")] TextureSettings);
+
+#[derive(Derivative, Clone, Serialize, Deserialize)]
+#[derivative(Debug)]
+/// A list of texture paths loaded in a `TextureRegistry`, along with the settings they were loaded with
 pub struct TextureList {
     assets_path: PathBuf,
     default_texture_path: PathBuf,
+    /// The textures loaded with the default settings
     other_texture_paths: Vec<PathBuf>,
+    /// Lists from before settings were saved were always loaded with "Nearest" filtering
+    #[derivative(Debug = "ignore")]
+    #[serde(with = "TextureSettingsDef", default = "nearest_settings")]
+    default_settings: TextureSettings,
+    /// The textures loaded with [TextureRegistry::load_with_settings]
+    #[derivative(Debug = "ignore")]
+    #[serde(default)]
+    settings: HashMap<PathBuf, SavedTextureSettings>,
 }
 
 impl From<TextureRegistry> for TextureList {
     fn from(value: TextureRegistry) -> Self {
-        Self {
-            assets_path: value.assets_path,
-            default_texture_path: value.default_path_texture.path().clone(),
-            other_texture_paths: value.textures.keys().map(|k| k.clone()).collect(),
-        }
+        Self::from(&value)
     }
 }
 
@@ -142,7 +262,177 @@ impl From<&TextureRegistry> for TextureList {
         Self {
             assets_path: value.assets_path.clone(),
             default_texture_path: value.default_path_texture.path().clone(),
-            other_texture_paths: value.textures.keys().map(|k| k.clone()).collect(),
+            other_texture_paths: value
+                .textures
+                .keys()
+                .filter(|k| !value.settings.contains_key(*k))
+                .cloned()
+                .collect(),
+            default_settings: value.default_settings,
+            settings: value
+                .settings
+                .iter()
+                .map(|(k, s)| (k.clone(), SavedTextureSettings(*s)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use graphics::ImageSize;
+
+    use super::*;
+    use crate::test_utils::{create_test_assets, gl_window, write_png};
+
+    #[test]
+    fn texture_list_keeps_filters() {
+        let mut settings = HashMap::new();
+        settings.insert(
+            PathBuf::from("background.png"),
+            SavedTextureSettings(nearest_settings()),
+        );
+        let list = TextureList {
+            assets_path: "assets".into(),
+            default_texture_path: "default.png".into(),
+            other_texture_paths: vec!["sprite.png".into()],
+            default_settings: TextureSettings::new().filter(Filter::Linear),
+            settings,
+        };
+
+        let serialized = serde_json::to_string(&list).unwrap();
+        let loaded: TextureList = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(loaded.default_settings.get_min(), Filter::Linear);
+        assert_eq!(loaded.default_settings.get_mag(), Filter::Linear);
+        let background = loaded.settings[&PathBuf::from("background.png")].0;
+        assert_eq!(background.get_min(), Filter::Nearest);
+        assert_eq!(background.get_mag(), Filter::Nearest);
+
+        // Lists saved before the settings were are loaded like they used to be
+        let old: TextureList = serde_json::from_str(
+            r#"{"assets_path":"assets","default_texture_path":"default.png","other_texture_paths":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(old.default_settings.get_min(), Filter::Nearest);
+        assert!(old.settings.is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn registry_loads_back_under_either_filter() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("filters");
+        let sprite_path = PathBuf::from("sprite.png");
+
+        for (default_filter, other_filter) in [
+            (Filter::Nearest, Filter::Linear),
+            (Filter::Linear, Filter::Nearest),
+        ] {
+            let mut registry = TextureRegistry::new(
+                assets_path.clone(),
+                "default.png".into(),
+                &[],
+                default_filter,
+            )
+            .unwrap();
+            registry
+                .load_with_settings(
+                    sprite_path.clone(),
+                    TextureSettings::new().filter(other_filter),
+                )
+                .unwrap();
+
+            let serialized = serde_json::to_string(&TextureList::from(&registry)).unwrap();
+            let loaded: TextureRegistry = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(loaded.default_settings.get_min(), default_filter);
+            assert_eq!(loaded.settings[&sprite_path].get_min(), other_filter);
+            assert!(loaded.get(&sprite_path).is_some());
         }
     }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn unloaded_texture_falls_back_to_default() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("unload");
+        let default_path = PathBuf::from("default.png");
+        let sprite_path = PathBuf::from("sprite.png");
+        let mut registry = TextureRegistry::new_nearest(
+            assets_path,
+            default_path.clone(),
+            std::slice::from_ref(&sprite_path),
+        )
+        .unwrap();
+        let mut loaded: Vec<&PathBuf> = registry.loaded_paths().collect();
+        loaded.sort();
+        assert_eq!(loaded, vec![&default_path, &sprite_path]);
+
+        assert!(registry.unload(&sprite_path).is_some());
+        assert!(registry.get(&sprite_path).is_none());
+        assert_eq!(registry.get_or_default(&sprite_path).get_size(), (1, 1));
+        assert_eq!(
+            registry.loaded_paths().collect::<Vec<_>>(),
+            vec![&default_path]
+        );
+        assert!(registry.unload(&sprite_path).is_none());
+    }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn unloading_default_texture_does_nothing() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("unload-default");
+        let default_path = PathBuf::from("default.png");
+        let mut registry =
+            TextureRegistry::new_nearest(assets_path, default_path.clone(), &[]).unwrap();
+
+        assert!(registry.unload(&default_path).is_none());
+        assert!(registry.get(&default_path).is_some());
+        assert_eq!(
+            registry.loaded_paths().collect::<Vec<_>>(),
+            vec![&default_path]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn reload_picks_up_edited_texture() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("reload");
+        let sprite_path = PathBuf::from("sprite.png");
+        let mut registry = TextureRegistry::new_nearest(
+            assets_path.clone(),
+            "default.png".into(),
+            std::slice::from_ref(&sprite_path),
+        )
+        .unwrap();
+        assert_eq!(registry.get(&sprite_path).unwrap().get_size(), (2, 2));
+
+        write_png(&assets_path.join(&sprite_path), 4, 3);
+        registry.reload(&sprite_path).unwrap();
+        assert_eq!(registry.get(&sprite_path).unwrap().get_size(), (4, 3));
+
+        write_png(&assets_path.join("default.png"), 2, 1);
+        registry.reload_all().unwrap();
+        assert_eq!(
+            registry.get(&"default.png".into()).unwrap().get_size(),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn reloading_unloaded_path_is_an_error() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("reload-unloaded");
+        let mut registry =
+            TextureRegistry::new_nearest(assets_path, "default.png".into(), &[]).unwrap();
+
+        // The file exists, but was never loaded into the registry
+        let path = PathBuf::from("sprite.png");
+        assert!(matches!(
+            registry.reload(&path),
+            Err(RenderError::TextureNotLoaded(p)) if p == path
+        ));
+    }
 }
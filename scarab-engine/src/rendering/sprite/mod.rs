@@ -3,7 +3,10 @@ use core::{fmt::Debug, marker::PhantomData};
 use std::{collections::HashMap, hash::Hash, path::PathBuf, time::Instant};
 
 use derivative::Derivative;
-use graphics::{Image, ImageSize, Transformed};
+use graphics::{
+    types::{Color, Matrix2d},
+    Image, ImageSize, Transformed,
+};
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
 use shapes::{Point, Size};
@@ -16,7 +19,7 @@ use crate::{
     ScarabResult,
 };
 
-mod sprite_serde;
+pub(crate) mod sprite_serde;
 
 #[derive(Derivative, Clone, Serialize, Deserialize)]
 #[derivative(Debug)]
@@ -29,6 +32,15 @@ pub struct SpriteView {
     #[serde(with = "ImageDef")]
     image: Image,
     texture_path: PathBuf,
+    /// Where the sprite's frames start in the texture, i.e. the corner of its region in a [SpriteAtlas]
+    #[serde(default = "default_src_origin")]
+    src_origin: Point,
+    /// Whether the sprite is mirrored horizontally
+    #[serde(default)]
+    flip_x: bool,
+    /// Whether the sprite is mirrored vertically
+    #[serde(default)]
+    flip_y: bool,
 }
 
 impl SpriteView {
@@ -42,13 +54,65 @@ impl SpriteView {
                 .rect([0.0, 0.0, sprite_size.w, sprite_size.h])
                 .src_rect([0.0, 0.0, sprite_size.w, sprite_size.h]),
             texture_path,
+            src_origin: default_src_origin(),
+            flip_x: false,
+            flip_y: false,
         })
     }
 
+    /// Creates a SpriteView of the atlas region with the given name, sized to the region.
+    /// Animating the sprite steps through frames starting from the region's corner.
+    /// Fails if the atlas has no region with that name
+    pub fn from_atlas(atlas: &SpriteAtlas, region_name: &str) -> RenderResult<Self> {
+        let [x, y, w, h] = *atlas
+            .regions
+            .get(region_name)
+            .ok_or_else(|| RenderError::UnknownAtlasRegion(region_name.to_string()))?;
+
+        let mut sprite = Self::new([0.0, 0.0].into(), [w, h].into(), atlas.texture_path.clone())?;
+        sprite.src_origin = [x, y].into();
+        sprite.set_src_rect_pos([0.0, 0.0].into());
+        Ok(sprite)
+    }
+
+    /// Sets whether the sprite is mirrored horizontally (i.e. to face left instead of right)
+    pub fn set_flip_x(&mut self, flip_x: bool) {
+        self.flip_x = flip_x;
+    }
+
+    /// Sets whether the sprite is mirrored vertically
+    pub fn set_flip_y(&mut self, flip_y: bool) {
+        self.flip_y = flip_y;
+    }
+
+    /// Sets the color the sprite is multiplied by when it's drawn, i.e. red for a damage flash
+    /// or a lower alpha to fade it out. The default of opaque white draws the sprite unchanged
+    pub fn set_tint(&mut self, tint: Color) {
+        self.image.color = Some(tint);
+    }
+
+    /// The color the sprite is multiplied by when it's drawn
+    pub fn tint(&self) -> Color {
+        self.image.color.unwrap_or([1.0; 4])
+    }
+
+    /// Mirrors the sprite's transform within its own rectangle for each flipped axis
+    fn flip_transform(&self, transform: Matrix2d) -> Matrix2d {
+        let mut transform = transform;
+        if self.flip_x {
+            transform = transform.trans(self.sprite_size.w, 0.0).flip_h();
+        }
+        if self.flip_y {
+            transform = transform.trans(0.0, self.sprite_size.h).flip_v();
+        }
+        transform
+    }
+
+    /// Sets the top left corner of the part of the texture that's drawn, relative to the sprite's origin
     fn set_src_rect_pos(&mut self, new_pos: Point) {
         if let Some(rect) = self.image.source_rectangle.as_mut() {
-            rect[0] = new_pos.x;
-            rect[1] = new_pos.y;
+            rect[0] = self.src_origin.x + new_pos.x;
+            rect[1] = self.src_origin.y + new_pos.y;
         }
     }
 
@@ -63,9 +127,11 @@ impl SpriteView {
     ) -> RenderResult<()> {
         if let Some((transform, _rect)) = camera.box_renderables(viewed.get_box(), ctx) {
             let scale_factor = camera.points_per_pixel();
-            let transform = transform
-                .trans_pos(self.pos * -scale_factor)
-                .scale(scale_factor, scale_factor);
+            let transform = self.flip_transform(
+                transform
+                    .trans_pos(self.pos * -scale_factor)
+                    .scale(scale_factor, scale_factor),
+            );
 
             self.image.draw(
                 texture_registry.get_or_default(&self.texture_path),
@@ -78,6 +144,39 @@ impl SpriteView {
     }
 }
 
+fn default_src_origin() -> Point {
+    [0.0, 0.0].into()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A single texture packed with many named sprites.
+/// Use [SpriteView::from_atlas] to create a view of one of its regions
+pub struct SpriteAtlas {
+    /// The path to the packed texture
+    pub texture_path: PathBuf,
+    /// The source rect (x, y, w, h) of each sprite in the texture by name
+    pub regions: HashMap<String, [f64; 4]>,
+}
+
+impl SpriteAtlas {
+    /// Creates an atlas over the given texture with no regions
+    pub fn new(texture_path: PathBuf) -> Self {
+        Self {
+            texture_path,
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Names the given source rect (x, y, w, h) of the texture, replacing any region with the same name
+    pub fn add_region(&mut self, name: impl Into<String>, rect: [f64; 4]) {
+        self.regions.insert(name.into(), rect);
+    }
+}
+
+fn default_looping() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A view type for displaying an animation across a single sprite map
 /// Should generally be used wrapped by an [AnimationStateMachine]
@@ -92,6 +191,12 @@ pub struct SpriteAnimation {
     milliseconds_per_frame: f64,
     /// The axis within the sprite map that adding to gets to the next frame
     animation_direction: Axis,
+    /// Whether the animation starts over after the last frame, or stays on it
+    #[serde(default = "default_looping")]
+    looping: bool,
+    /// Whether a non-looping animation has played all of its frames
+    #[serde(skip)]
+    finished: bool,
     /// The timestamp at which the last frame was set
     #[serde(skip)]
     #[serde(default = "Instant::now")]
@@ -125,7 +230,11 @@ impl SpriteAnimation {
 
         let frames_in_sprite_map = if let Some(frames) = frames_in_sprite_map {
             if frames > max_num_frames {
-                return Err(AnimationError::TooManyFrames(frames, max_num_frames).into());
+                return Err(AnimationError::TooManyFrames {
+                    requested: frames,
+                    max: max_num_frames,
+                }
+                .into());
             } else {
                 frames
             }
@@ -139,6 +248,8 @@ impl SpriteAnimation {
             frame_num: 0,
             milliseconds_per_frame,
             animation_direction,
+            looping: true,
+            finished: false,
             last_update: Instant::now(),
         })
     }
@@ -151,39 +262,85 @@ impl SpriteAnimation {
             frame_num: 0,
             milliseconds_per_frame: 1000.0,
             animation_direction: Axis::X,
+            looping: true,
+            finished: false,
             last_update: Instant::now(),
         }
     }
 
+    /// Sets whether the animation is mirrored horizontally, i.e. to face the direction of movement
+    pub fn set_flip_x(&mut self, flip_x: bool) {
+        self.sprite.set_flip_x(flip_x);
+    }
+
+    /// Sets whether the animation is mirrored vertically
+    pub fn set_flip_y(&mut self, flip_y: bool) {
+        self.sprite.set_flip_y(flip_y);
+    }
+
+    /// Sets the color every frame of the animation is multiplied by. See [SpriteView::set_tint]
+    pub fn set_tint(&mut self, tint: Color) {
+        self.sprite.set_tint(tint);
+    }
+
+    /// The color every frame of the animation is multiplied by
+    pub fn tint(&self) -> Color {
+        self.sprite.tint()
+    }
+
+    /// Sets whether the animation starts over after its last frame (the default),
+    /// or plays once and stays on its last frame
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Whether a non-looping animation has played all of its frames.
+    /// Looping animations are never finished
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
     /// Prepares the animation to be started again.
     fn reset(&mut self) {
         self.frame_num = 0;
+        self.finished = false;
         self.last_update = Instant::now()
     }
 
-    fn render<V: HasBox>(
-        &mut self,
-        viewed: &V,
-        args: &RenderArgs,
-        camera: &Camera,
-        ctx: graphics::Context,
-        texture_registry: &TextureRegistry,
-        gl: &mut opengl_graphics::GlGraphics,
-    ) -> RenderResult<()> {
-        // args.ext_dt is a liar, so we calculate our own dt
-        let now = Instant::now();
-
+    /// Moves to the frame that should be showing at `now`
+    fn advance(&mut self, now: Instant) {
         let num_new_frames =
             ((now - self.last_update).as_millis() / self.milliseconds_per_frame as u128) as usize;
         if num_new_frames > 0 && self.frames_in_sprite_map > 0 {
             self.last_update = now;
-            self.frame_num = (self.frame_num + num_new_frames) % self.frames_in_sprite_map;
+            let next_frame = self.frame_num + num_new_frames;
+            self.frame_num = if self.looping {
+                next_frame % self.frames_in_sprite_map
+            } else if next_frame >= self.frames_in_sprite_map {
+                self.finished = true;
+                self.frames_in_sprite_map - 1
+            } else {
+                next_frame
+            };
             let new_pos = match self.animation_direction {
                 Axis::X => [self.frame_num as f64 * self.sprite.sprite_size.w, 0.0].into(),
                 Axis::Y => [0.0, self.frame_num as f64 * self.sprite.sprite_size.h].into(),
             };
             self.sprite.set_src_rect_pos(new_pos)
         }
+    }
+
+    fn render<V: HasBox>(
+        &mut self,
+        viewed: &V,
+        args: &RenderArgs,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+    ) -> RenderResult<()> {
+        // args.ext_dt is a liar, so we calculate our own dt
+        self.advance(Instant::now());
 
         self.sprite
             .render(viewed, args, camera, ctx, texture_registry, gl)
@@ -195,18 +352,28 @@ impl SpriteAnimation {
 pub struct AnimationStateMachine<S: AnimationStates> {
     current_state: S,
     animations: HashMap<S, SpriteAnimation>,
+    /// The state to move to once each state's (non-looping) animation finishes
+    #[serde(default = "HashMap::new")]
+    on_finish: HashMap<S, S>,
 }
 
 impl<S: AnimationStates> AnimationStateMachine<S> {
     /// The 'animations' must have an entry for 'initial_state'
     pub fn new(initial_state: S, animations: HashMap<S, SpriteAnimation>) -> ScarabResult<Self> {
-        let _ = animations
-            .get(&initial_state)
-            .ok_or_else(|| AnimationError::NoAnimationForState(format!("{:?}", initial_state)));
+        if !animations.contains_key(&initial_state) {
+            return Err(
+                RenderError::from(AnimationError::NoAnimationForState(format!(
+                    "{:?}",
+                    initial_state
+                )))
+                .into(),
+            );
+        }
 
         Ok(Self {
             current_state: initial_state,
             animations,
+            on_finish: HashMap::new(),
         })
     }
 
@@ -215,6 +382,45 @@ impl<S: AnimationStates> AnimationStateMachine<S> {
         self.animations.insert(state, animation);
     }
 
+    /// Once the animation for `finished` is done, automatically move to `next`.
+    /// Only applies to animations that don't [loop](SpriteAnimation::set_looping)
+    pub fn set_on_finish(&mut self, finished: S, next: S) {
+        self.on_finish.insert(finished, next);
+    }
+
+    /// Tints the animation of every state, so the tint stays when the state changes.
+    /// See [SpriteView::set_tint]
+    pub fn set_tint(&mut self, tint: Color) {
+        for animation in self.animations.values_mut() {
+            animation.set_tint(tint);
+        }
+    }
+
+    /// The state currently being rendered
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// Moves to the next state, either from the viewed object or because the
+    /// current animation finished
+    fn update_state(&mut self, viewed: &S::Viewed) -> Result<(), AnimationError> {
+        if let Some(next) = self.current_state.next_state(viewed) {
+            self.set_current_state(next)?;
+        }
+
+        let finished = self
+            .animations
+            .get(&self.current_state)
+            .is_some_and(|a| a.is_finished());
+        if finished {
+            if let Some(next) = self.on_finish.get(&self.current_state).cloned() {
+                self.set_current_state(next)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Sets the current state to new_state.
     /// Fails if there is no animation for new_state
     pub fn set_current_state(&mut self, new_state: S) -> Result<(), AnimationError> {
@@ -241,12 +447,13 @@ impl<E: HasBox> AnimationStateMachine<StaticAnimation<E>> {
         Self {
             current_state,
             animations,
+            on_finish: HashMap::new(),
         }
     }
 }
 
 /// Defines the set of states which an [AnimationStateMachine] can render
-pub trait AnimationStates: Debug + Eq + Hash
+pub trait AnimationStates: Debug + Clone + Eq + Hash
 where
     Self: Sized,
 {
@@ -294,14 +501,316 @@ impl<S: AnimationStates> View for AnimationStateMachine<S> {
         texture_registry: &TextureRegistry,
         gl: &mut opengl_graphics::GlGraphics,
     ) -> RenderResult<()> {
-        self.current_state
-            .next_state(viewed)
-            .map_or(Ok(()), |s| self.set_current_state(s))
-            .unwrap_or_else(|e| {
-                println!("Error rendering animated sprite for {:?}: {:}", self, e);
-            });
+        self.update_state(viewed).unwrap_or_else(|e| {
+            println!("Error rendering animated sprite for {:?}: {:}", self, e);
+        });
 
         let animation = self.animations.get_mut(&self.current_state).unwrap();
         animation.render(viewed, args, camera, ctx, texture_registry, gl)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use opengl_graphics::Filter;
+
+    use super::*;
+    use crate::{
+        test_utils::{create_test_assets, gl_window},
+        types::physbox::PhysBox,
+        ScarabError, ScarabResult,
+    };
+
+    /// A 4 frame animation at 100ms per frame
+    fn create_test_animation(looping: bool) -> SpriteAnimation {
+        let sprite =
+            SpriteView::new([0.0, 0.0].into(), [8.0, 8.0].into(), "test.png".into()).unwrap();
+        let mut animation = SpriteAnimation::new_static_frame(sprite);
+        animation.frames_in_sprite_map = 4;
+        animation.milliseconds_per_frame = 100.0;
+        animation.set_looping(looping);
+        animation
+    }
+
+    #[test]
+    fn tint_is_drawn_through_image_color() {
+        let mut animation = create_test_animation(true);
+        assert_eq!(animation.tint(), [1.0; 4]);
+        assert_eq!(animation.sprite.image.color, None);
+
+        animation.set_tint([1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(animation.tint(), [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(animation.sprite.image.color, Some([1.0, 0.0, 0.0, 0.5]));
+
+        let mut animations = HashMap::new();
+        animations.insert(StaticAnimation::<PhysBox>::default(), animation);
+        let mut machine =
+            AnimationStateMachine::new(StaticAnimation::default(), animations).unwrap();
+        machine.set_tint([0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(
+            machine.animations[machine.current_state()].tint(),
+            [0.0, 1.0, 0.0, 1.0]
+        );
+    }
+
+    /// A viewed type that isn't an entity and doesn't derive anything
+    struct Door {
+        physbox: PhysBox,
+    }
+
+    impl HasBox for Door {
+        fn get_box(&self) -> &PhysBox {
+            &self.physbox
+        }
+    }
+
+    #[test]
+    fn static_animation_over_custom_viewed_type() {
+        let sprite =
+            SpriteView::new([0.0, 0.0].into(), [8.0, 8.0].into(), "door.png".into()).unwrap();
+        let machine: AnimationStateMachine<StaticAnimation<Door>> =
+            AnimationStateMachine::static_animation(SpriteAnimation::new_static_frame(sprite));
+        let door = Door {
+            physbox: PhysBox::new([0.0, 0.0, 8.0, 16.0]).unwrap(),
+        };
+
+        assert_eq!(machine.current_state(), &StaticAnimation::default());
+        assert_eq!(machine.current_state().next_state(&door), None);
+    }
+
+    #[test]
+    fn missing_initial_state_is_a_matchable_error() {
+        match AnimationStateMachine::new(TestStates::Idle, HashMap::new()) {
+            Err(ScarabError::RenderingError(RenderError::AnimationError(
+                AnimationError::NoAnimationForState(state),
+            ))) => assert_eq!(state, "Idle"),
+            other => panic!("expected a missing animation state, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn render_errors_are_matchable_as_scarab_errors() {
+        let too_many: ScarabError = RenderError::from(AnimationError::TooManyFrames {
+            requested: 5,
+            max: 4,
+        })
+        .into();
+        assert_eq!(
+            too_many.to_string(),
+            "Requested number of frames (5) is too many for sprite sheet (4)"
+        );
+        match too_many {
+            ScarabError::RenderingError(RenderError::AnimationError(
+                AnimationError::TooManyFrames { requested, max },
+            )) => assert_eq!((requested, max), (5, 4)),
+            other => panic!("expected too many frames, got {other:?}"),
+        }
+
+        let not_loaded: ScarabError = RenderError::TextureNotLoaded("missing.png".into()).into();
+        assert_eq!(
+            not_loaded.to_string(),
+            "The texture 'missing.png' is not loaded"
+        );
+        match not_loaded {
+            ScarabError::RenderingError(RenderError::TextureNotLoaded(path)) => {
+                assert_eq!(path, PathBuf::from("missing.png"))
+            }
+            other => panic!("expected an unloaded texture, got {other:?}"),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GL context"]
+    fn new_animation_reports_matchable_errors() {
+        let _window = gl_window();
+        let assets_path = create_test_assets("sprite-errors");
+        let registry = TextureRegistry::new(
+            assets_path,
+            "default.png".into(),
+            &["sprite.png".into()],
+            Filter::Nearest,
+        )
+        .unwrap();
+        let new_animation = |path: &str, frames| -> ScarabResult<SpriteAnimation> {
+            Ok(SpriteAnimation::new(
+                [0.0, 0.0].into(),
+                [1.0, 1.0].into(),
+                path.into(),
+                100.0,
+                Axis::X,
+                frames,
+                &registry,
+            )?)
+        };
+
+        // sprite.png is 2x2, so fits 2 frames along x
+        assert_eq!(
+            new_animation("sprite.png", None)
+                .unwrap()
+                .frames_in_sprite_map,
+            2
+        );
+        match new_animation("sprite.png", Some(3)) {
+            Err(ScarabError::RenderingError(RenderError::AnimationError(
+                AnimationError::TooManyFrames { requested, max },
+            ))) => assert_eq!((requested, max), (3, 2)),
+            other => panic!("expected too many frames, got {other:?}"),
+        }
+        match new_animation("missing.png", None) {
+            Err(ScarabError::RenderingError(RenderError::TextureNotLoaded(path))) => {
+                assert_eq!(path, PathBuf::from("missing.png"))
+            }
+            other => panic!("expected an unloaded texture, got {other:?}"),
+        }
+    }
+
+    fn advance_by(animation: &mut SpriteAnimation, millis: u64) {
+        let now = animation.last_update + Duration::from_millis(millis);
+        animation.advance(now);
+    }
+
+    /// Where the transform puts the given point
+    fn apply(transform: Matrix2d, [x, y]: [f64; 2]) -> [f64; 2] {
+        [
+            transform[0][0] * x + transform[0][1] * y + transform[0][2],
+            transform[1][0] * x + transform[1][1] * y + transform[1][2],
+        ]
+    }
+
+    #[test]
+    fn flipped_sprite_mirrors_unflipped() {
+        let mut sprite =
+            SpriteView::new([0.0, 0.0].into(), [8.0, 6.0].into(), "test.png".into()).unwrap();
+        let base = graphics::Context::new()
+            .transform
+            .trans(100.0, 50.0)
+            .scale(2.0, 2.0);
+        let unflipped = sprite.flip_transform(base);
+        assert_eq!(unflipped, base);
+
+        sprite.set_flip_x(true);
+        let flipped_x = sprite.flip_transform(base);
+        for [x, y] in [[0.0, 0.0], [8.0, 6.0], [3.0, 1.0]] {
+            assert_eq!(apply(flipped_x, [x, y]), apply(unflipped, [8.0 - x, y]));
+        }
+
+        sprite.set_flip_x(false);
+        sprite.set_flip_y(true);
+        let flipped_y = sprite.flip_transform(base);
+        for [x, y] in [[0.0, 0.0], [8.0, 6.0], [3.0, 1.0]] {
+            assert_eq!(apply(flipped_y, [x, y]), apply(unflipped, [x, 6.0 - y]));
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum TestStates {
+        Idle,
+        Attack,
+    }
+
+    impl AnimationStates for TestStates {
+        type Viewed = PhysBox;
+
+        fn next_state(&self, _viewed: &Self::Viewed) -> Option<Self> {
+            None
+        }
+    }
+
+    #[test]
+    fn finished_animation_moves_to_on_finish_state() {
+        let mut animations = HashMap::new();
+        animations.insert(TestStates::Idle, create_test_animation(true));
+        animations.insert(TestStates::Attack, create_test_animation(false));
+        let mut asm = AnimationStateMachine::new(TestStates::Idle, animations).unwrap();
+        asm.set_on_finish(TestStates::Attack, TestStates::Idle);
+        let viewed = PhysBox::new([0.0, 0.0, 8.0, 8.0]).unwrap();
+
+        asm.set_current_state(TestStates::Attack).unwrap();
+        let attack = asm.animations.get_mut(&TestStates::Attack).unwrap();
+        advance_by(attack, 300);
+        asm.update_state(&viewed).unwrap();
+        assert_eq!(*asm.current_state(), TestStates::Attack);
+
+        let attack = asm.animations.get_mut(&TestStates::Attack).unwrap();
+        advance_by(attack, 100);
+        asm.update_state(&viewed).unwrap();
+        assert_eq!(*asm.current_state(), TestStates::Idle);
+    }
+
+    #[test]
+    fn looping_animation_wraps_around() {
+        let mut animation = create_test_animation(true);
+
+        advance_by(&mut animation, 250);
+        assert_eq!(animation.frame_num, 2);
+        advance_by(&mut animation, 250);
+        assert_eq!(animation.frame_num, 0);
+        advance_by(&mut animation, 1000);
+        assert_eq!(animation.frame_num, 2);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn play_once_animation_stops_on_last_frame() {
+        let mut animation = create_test_animation(false);
+
+        advance_by(&mut animation, 300);
+        assert_eq!(animation.frame_num, 3);
+        // The last frame still gets shown for its full duration
+        assert!(!animation.is_finished());
+
+        advance_by(&mut animation, 100);
+        assert_eq!(animation.frame_num, 3);
+        assert!(animation.is_finished());
+
+        advance_by(&mut animation, 1000);
+        assert_eq!(animation.frame_num, 3);
+        assert!(animation.is_finished());
+
+        animation.reset();
+        assert_eq!(animation.frame_num, 0);
+        assert!(!animation.is_finished());
+    }
+
+    fn create_test_atlas() -> SpriteAtlas {
+        let mut atlas = SpriteAtlas::new("atlas.png".into());
+        atlas.add_region("player", [16.0, 32.0, 8.0, 12.0]);
+        atlas.add_region("enemy", [0.0, 64.0, 16.0, 16.0]);
+        atlas
+    }
+
+    #[test]
+    fn atlas_region_sets_source_rect() {
+        let atlas = create_test_atlas();
+        let sprite = SpriteView::from_atlas(&atlas, "player").unwrap();
+
+        assert_eq!(sprite.image.source_rectangle, Some([16.0, 32.0, 8.0, 12.0]));
+        assert_eq!(sprite.image.rectangle, Some([0.0, 0.0, 8.0, 12.0]));
+        assert_eq!(sprite.texture_path, PathBuf::from("atlas.png"));
+    }
+
+    #[test]
+    fn unknown_atlas_region_is_an_error() {
+        let atlas = create_test_atlas();
+        let err = SpriteView::from_atlas(&atlas, "boss").unwrap_err();
+
+        assert!(matches!(err, RenderError::UnknownAtlasRegion(ref name) if name == "boss"));
+    }
+
+    #[test]
+    fn atlas_animation_frames_start_at_region() {
+        let atlas = create_test_atlas();
+        let sprite = SpriteView::from_atlas(&atlas, "enemy").unwrap();
+        let mut animation = SpriteAnimation::new_static_frame(sprite);
+        animation.frames_in_sprite_map = 4;
+        animation.milliseconds_per_frame = 100.0;
+
+        advance_by(&mut animation, 200);
+        assert_eq!(
+            animation.sprite.image.source_rectangle,
+            Some([32.0, 64.0, 16.0, 16.0])
+        );
+    }
+}
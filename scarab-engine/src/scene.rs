@@ -1,30 +1,72 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 use graphics::Context;
 use opengl_graphics::GlGraphics;
+use petgraph::graph::NodeIndex;
 use piston::RenderArgs;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use shapes::Point;
+use uuid::Uuid;
 
+#[cfg(feature = "profiling")]
+use crate::profiling::{self, FrameProfile};
 use crate::{
+    effect::EffectTarget,
     gameobject::{
-        entity::registry::{EntityRegistry, RegisteredDebugEntity, RegisteredEntity},
-        field::Field,
-        HasSolidity,
+        entity::registry::{EntityHandle, EntityRegistry, RegisteredDebugEntity, RegisteredEntity},
+        field::{Cell, Field},
+        HasHealth, HasSolidity, NO_SOLIDITY,
     },
-    rendering::{debug::DebugView, registry::TextureRegistry, Camera, View},
-    types::physbox::{HasBox, HasBoxMut, PhysBox},
-    ScarabResult,
+    rendering::{debug::DebugView, registry::TextureRegistry, Camera, RenderStats, View},
+    types::{
+        physbox::{HasBox, HasBoxMut, PhysBox},
+        Velocity,
+    },
+    PhysicsError, PhysicsResult, ScarabResult,
 };
 
+/// Evaluates `$body`, recording how long it took in the `$profile` [FrameProfile] under `$name`.
+/// Without the "profiling" feature this is only `$body`
+macro_rules! profile_section {
+    ($profile:expr, $name:expr, $body:expr) => {{
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        let res = $body;
+        #[cfg(feature = "profiling")]
+        $profile.record($name, start.elapsed());
+        res
+    }};
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 /// A wrapper over all things in the app right now
 pub struct Scene<E, V> {
     field: Field,
     field_view: V,
+    #[serde(default = "Option::default")]
+    foreground_view: Option<V>,
     entity_registry: EntityRegistry<E>,
     #[serde(skip)]
     #[serde(default = "Vec::new")]
     pending_attacks: Vec<PendingEffect<E>>,
+    #[serde(default)]
+    cull_dead: bool,
+    #[serde(skip)]
+    accumulator: f64,
+    /// The number of fixed steps [Scene::update] has run
+    #[serde(default)]
+    fixed_steps: u64,
+    #[serde(default = "default_time_scale")]
+    time_scale: f64,
+    #[serde(default)]
+    paused: bool,
+    /// Saved with the scene so that a loaded scene keeps drawing the same values
+    #[serde(default = "SceneRng::from_entropy")]
+    rng: SceneRng,
+    #[cfg(feature = "profiling")]
+    #[serde(skip)]
+    frame_profile: FrameProfile,
 }
 
 impl<E, V> Scene<E, V>
@@ -34,15 +76,40 @@ where
 {
     /// Initializes a new scene with the given field, field view and no entities
     pub fn new(field: Field, field_view: V) -> Self {
+        Self::with_rng(field, field_view, SceneRng::from_entropy())
+    }
+
+    /// Initializes a new scene like [Scene::new], but with its random number generator seeded by `seed`
+    /// so that everything drawing from [Scene::rng_mut] is reproducible
+    pub fn with_seed(field: Field, field_view: V, seed: u64) -> Self {
+        Self::with_rng(field, field_view, SceneRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(field: Field, field_view: V, rng: SceneRng) -> Self {
         Self {
             field,
             field_view,
+            foreground_view: None,
             entity_registry: EntityRegistry::default(),
             pending_attacks: Vec::default(),
+            cull_dead: false,
+            accumulator: 0.0,
+            fixed_steps: 0,
+            time_scale: 1.0,
+            paused: false,
+            rng,
+            #[cfg(feature = "profiling")]
+            frame_profile: FrameProfile::default(),
         }
     }
 
-    /// Renders everything in the scene
+    /// The scene's random number generator, which effects and spawns should draw from
+    /// instead of [rand::thread_rng] to keep seeded scenes reproducible
+    pub fn rng_mut(&mut self) -> &mut SceneRng {
+        &mut self.rng
+    }
+
+    /// Renders everything in the scene, returning how many entities were on the camera
     pub fn render(
         &mut self,
         args: &RenderArgs,
@@ -50,18 +117,26 @@ where
         ctx: Context,
         texture_registry: &TextureRegistry,
         gl: &mut GlGraphics,
-    ) -> ScarabResult<()> {
-        self.field_view
-            .render(&mut self.field, args, &camera, ctx, texture_registry, gl)?;
+    ) -> ScarabResult<RenderStats> {
+        profile_section!(self.frame_profile, profiling::RENDER, {
+            self.field_view
+                .render(&mut self.field, args, &camera, ctx, texture_registry, gl)?;
 
-        for registered_entity in &mut self.entity_registry {
-            registered_entity.render(args, camera, ctx, texture_registry, gl)?;
-        }
-        Ok(())
+            for i in self.render_order() {
+                if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                    registered_entity.render(args, camera, ctx, texture_registry, gl)?;
+                }
+            }
+
+            if let Some(foreground_view) = &mut self.foreground_view {
+                foreground_view.render(&mut self.field, args, camera, ctx, texture_registry, gl)?;
+            }
+            Ok(self.render_stats(camera))
+        })
     }
 
     #[cfg(feature = "debug-rendering")]
-    /// Renders the scene with additional debug info
+    /// Renders the scene with additional debug info, returning how many entities were on the camera
     pub fn render_with_info<D>(
         &mut self,
         debug_options: &D,
@@ -70,37 +145,237 @@ where
         ctx: Context,
         texture_registry: &TextureRegistry,
         gl: &mut GlGraphics,
-    ) -> ScarabResult<()>
+    ) -> ScarabResult<RenderStats>
     where
         E: RegisteredDebugEntity<DebugOptions = D>,
         V: DebugView<Viewed = Field, DebugOptions = D>,
     {
-        self.field_view.render_with_info(
-            &mut self.field,
-            debug_options,
-            args,
-            &camera,
-            ctx,
-            texture_registry,
-            gl,
-        )?;
-
-        for registered_entity in &mut self.entity_registry {
-            registered_entity.render_with_info(
+        profile_section!(self.frame_profile, profiling::RENDER, {
+            self.field_view.render_with_info(
+                &mut self.field,
                 debug_options,
                 args,
-                camera,
+                &camera,
                 ctx,
                 texture_registry,
                 gl,
             )?;
+
+            for i in self.render_order() {
+                if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                    registered_entity.render_with_info(
+                        debug_options,
+                        args,
+                        camera,
+                        ctx,
+                        texture_registry,
+                        gl,
+                    )?;
+                }
+            }
+
+            if let Some(foreground_view) = &mut self.foreground_view {
+                foreground_view.render_with_info(
+                    &mut self.field,
+                    debug_options,
+                    args,
+                    camera,
+                    ctx,
+                    texture_registry,
+                    gl,
+                )?;
+            }
+            Ok(self.render_stats(camera))
+        })
+    }
+
+    /// Sets a second view of the field that's drawn over every entity, i.e. for treetops or overhangs.
+    /// The scene's main field view is always drawn under every entity
+    pub fn set_foreground_view(&mut self, foreground_view: Option<V>) {
+        self.foreground_view = foreground_view;
+    }
+
+    /// The registry indices of every entity in the order they're drawn, by [layer](RegisteredEntity::get_layer).
+    /// The sort is stable, so entities on the same layer keep their index order
+    fn render_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = self
+            .entity_registry
+            .iter_indexed()
+            .map(|(i, _)| i)
+            .collect();
+        order.sort_by_key(|i| {
+            self.entity_registry
+                .get_one(*i)
+                .map_or(0, |e| e.get_layer())
+        });
+        order
+    }
+
+    /// Counts the entities that are on the camera, and so actually get drawn
+    fn render_stats(&self, camera: &Camera) -> RenderStats {
+        let visible = camera.visible_box();
+        let drawn = self
+            .entity_registry
+            .iter()
+            .filter(|e| e.inner_entity().get_box().has_overlap(&visible))
+            .count();
+        RenderStats {
+            drawn,
+            culled: self.entity_registry.len() - drawn,
+        }
+    }
+
+    /// Registers a new entity to the scene, returning a handle to refer to it later.
+    /// Runs the entity's [RegisteredEntity::on_spawn], if that fails the entity is unregistered again
+    pub fn register_entity(&mut self, to_register: E) -> ScarabResult<EntityHandle> {
+        let handle = self.entity_registry.register(to_register)?;
+        if let Some(registered) = self.entity_registry.get_mut(handle) {
+            if let Err(e) = registered.on_spawn(&SpawnArgs {
+                field: &self.field,
+                handle,
+            }) {
+                self.entity_registry.remove(handle.uuid);
+                return Err(e);
+            }
         }
+        Ok(handle)
+    }
+
+    /// Removes the entity with the given uuid from the scene, returning it if it was registered.
+    /// Pending effects from the entity keep applying, but no longer have a source,
+    /// except for ones that [need their source](TargetsOthers::needs_source) which are dropped.
+    /// Runs the entity's [RegisteredEntity::on_despawn] first, if that fails the entity stays registered
+    pub fn despawn_entity(&mut self, uuid: Uuid) -> ScarabResult<Option<E>> {
+        let Some(handle) = self
+            .entity_registry
+            .index_of_uuid(uuid)
+            .and_then(|i| self.entity_registry.handle_at(i))
+        else {
+            return Ok(None);
+        };
+
+        if let Some(registered) = self.entity_registry.get_mut(handle) {
+            registered.on_despawn(&SpawnArgs {
+                field: &self.field,
+                handle,
+            })?;
+        }
+        Ok(self.entity_registry.remove(uuid))
+    }
+
+    /// Sets whether entities that have run out of health are despawned at the end of each [Scene::step].
+    /// Culling happens after pending effects are processed, see [Scene::despawn_entity] for what happens
+    /// to effects that persist across ticks
+    pub fn set_cull_dead(&mut self, cull_dead: bool) {
+        self.cull_dead = cull_dead;
+    }
+
+    /// Whether entities that have run out of health are despawned at the end of each [Scene::step]
+    pub fn cull_dead(&self) -> bool {
+        self.cull_dead
+    }
+
+    /// Gets a reference to the entity the handle refers to if it's still registered
+    pub fn get_entity(&self, handle: EntityHandle) -> Option<&E> {
+        self.entity_registry.get(handle)
+    }
+
+    /// Gets a mutable reference to the entity the handle refers to if it's still registered
+    pub fn get_entity_mut(&mut self, handle: EntityHandle) -> Option<&mut E> {
+        self.entity_registry.get_mut(handle)
+    }
+
+    /// The registry indices of all entities whose box overlaps `area`
+    pub fn entities_in_box(&self, area: &PhysBox) -> Vec<usize> {
+        self.entity_registry
+            .iter_indexed()
+            .filter(|(_, e)| e.inner_entity().get_box().has_overlap(area))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// All entities whose box overlaps `area`, along with their registry indices
+    pub fn entities_in_box_mut(&mut self, area: &PhysBox) -> Vec<(usize, &mut E)> {
+        self.entity_registry
+            .iter_indexed_mut()
+            .filter(|(_, e)| e.inner_entity().get_box().has_overlap(area))
+            .collect()
+    }
+
+    /// Casts a ray from `from` in the direction `dir`, returning the registry index of the
+    /// nearest entity whose box it enters within `max_dist`, along with the point where it enters.
+    /// Entities with [NO_SOLIDITY] can't be hit, see [Scene::raycast_entities_filtered] to choose which entities can be
+    pub fn raycast_entities(
+        &self,
+        from: Point,
+        dir: Velocity,
+        max_dist: f64,
+    ) -> Option<(usize, Point)> {
+        self.raycast_entities_filtered(from, dir, max_dist, |_, e| {
+            *e.inner_entity().get_solidity() != NO_SOLIDITY
+        })
+    }
+
+    /// Like [Scene::raycast_entities], but only entities that `filter` returns true for can be hit
+    pub fn raycast_entities_filtered<F>(
+        &self,
+        from: Point,
+        dir: Velocity,
+        max_dist: f64,
+        filter: F,
+    ) -> Option<(usize, Point)>
+    where
+        F: Fn(usize, &E) -> bool,
+    {
+        let to = from + dir.normalize() * max_dist;
+        if to == from {
+            return None;
+        }
+
+        self.entity_registry
+            .iter_indexed()
+            .filter(|(i, e)| filter(*i, e))
+            .filter_map(|(i, e)| {
+                e.inner_entity()
+                    .get_box()
+                    .segment_entry(from, to)
+                    .map(|t| (i, t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, t)| {
+                (
+                    i,
+                    [from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t].into(),
+                )
+            })
+    }
+
+    /// Sets the multiplier applied to the `dt` of every [Scene::step], i.e. 0.5 for slow motion.
+    /// Must be positive and finite, use [Scene::set_paused] to stop time instead of a scale of 0. This only scales the simulation: [SpriteAnimation](crate::rendering::sprite::SpriteAnimation)s
+    /// are timed by the wall clock and keep their normal speed
+    pub fn set_time_scale(&mut self, time_scale: f64) -> PhysicsResult<()> {
+        if !(time_scale > 0.0 && time_scale.is_finite()) {
+            return Err(PhysicsError::TimeScale);
+        }
+        self.time_scale = time_scale;
         Ok(())
     }
 
-    /// Registers a new entity to the scene
-    pub fn register_entity(&mut self, to_register: E) -> ScarabResult<()> {
-        self.entity_registry.register(to_register)
+    /// The multiplier applied to the `dt` of every [Scene::step]
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Pauses or resumes the simulation. While paused [Scene::step] does nothing,
+    /// but the scene can still be rendered.
+    /// As with [Scene::set_time_scale], wall clock timed animations keep playing
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Whether the simulation is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
     }
 
     /// Gets a reference to the scene's [Field]
@@ -110,20 +385,144 @@ where
 
     /// Runs the physics update for all of the scene's entities
     pub fn tick_entities(&mut self, dt: f64) -> ScarabResult<()> {
-        let mut args = GameTickArgs {
-            field: &self.field,
-            pending_attacks: &mut self.pending_attacks,
-            dt,
-        };
-        for (i, registered_entity) in self.entity_registry.iter_mut().enumerate() {
-            registered_entity.game_tick(i, &mut args)?;
+        self.step(dt).map(|_| ())
+    }
+
+    /// Adds the frame's elapsed time to the scene and runs [Scene::tick_entities] with `fixed_dt`
+    /// as many whole times as fit, carrying the remainder over to the next frame.
+    /// This keeps physics the same no matter the frame rate.
+    /// Returns the number of fixed steps that were run
+    pub fn update(&mut self, frame_dt: f64, fixed_dt: f64) -> ScarabResult<usize> {
+        if fixed_dt <= 0.0 {
+            return Err(PhysicsError::FixedTimestep.into());
         }
 
-        self.handle_entity_collisions()?;
+        self.accumulator += frame_dt;
+        let mut steps = 0;
+        while self.accumulator >= fixed_dt {
+            self.tick_entities(fixed_dt)?;
+            self.accumulator -= fixed_dt;
+            self.fixed_steps += 1;
+            steps += 1;
+        }
 
-        self.process_pending_effects()?;
+        Ok(steps)
+    }
 
-        Ok(())
+    /// The total number of fixed steps that [Scene::update] has run,
+    /// i.e. to number the frames of an [InputRecorder](crate::input::InputRecorder)
+    pub fn fixed_step_count(&self) -> u64 {
+        self.fixed_steps
+    }
+
+    /// How far the scene is between the last fixed step and the next one, from 0 to 1.
+    /// `fixed_dt` should be the same one given to [Scene::update]
+    pub fn interpolation_alpha(&self, fixed_dt: f64) -> f64 {
+        if fixed_dt <= 0.0 {
+            return 0.0;
+        }
+        (self.accumulator / fixed_dt).clamp(0.0, 1.0)
+    }
+
+    /// The time (in seconds) that [Scene::update] has carried over, waiting for the next fixed step
+    pub fn accumulated_time(&self) -> f64 {
+        self.accumulator
+    }
+
+    /// Runs the physics update for all of the scene's entities, then resolves
+    /// collisions between entities and applies pending effects.
+    /// Returns everything of note that happened during the step
+    pub fn step(&mut self, dt: f64) -> ScarabResult<StepResult> {
+        if self.paused {
+            return Ok(StepResult::default());
+        }
+        let dt = dt * self.time_scale;
+
+        profile_section!(self.frame_profile, profiling::TICK_ENTITIES, {
+            let mut result = StepResult::default();
+            let cells_before: Vec<(usize, Option<NodeIndex>)> = self
+                .entity_registry
+                .iter_indexed()
+                .map(|(i, e)| (i, self.cell_index_of(e)))
+                .collect();
+
+            let mut spawn_queue = Vec::new();
+            let mut args = GameTickArgs {
+                field: &self.field,
+                pending_attacks: &mut self.pending_attacks,
+                spawn_queue: &mut spawn_queue,
+                dt,
+            };
+            profile_section!(self.frame_profile, profiling::ENTITY_TICKS, {
+                for (handle, registered_entity) in self.entity_registry.iter_handles_mut() {
+                    registered_entity.game_tick(handle, &mut args)?;
+
+                    // Catch floating-point errors where they happen rather than
+                    // after they've spread to everything else
+                    #[cfg(debug_assertions)]
+                    registered_entity.inner_entity().validate_state()?;
+                }
+            });
+            for to_spawn in spawn_queue {
+                let uuid = to_spawn.uuid();
+                self.register_entity(to_spawn)?;
+                result.spawned.push(uuid);
+            }
+
+            for (i, from) in cells_before {
+                let Some(registered_entity) = self.entity_registry.get_one(i) else {
+                    continue;
+                };
+                let to = self.cell_index_of(registered_entity);
+                if from != to {
+                    result.cell_transitions.push(CellTransition {
+                        entity: registered_entity.uuid(),
+                        from,
+                        to,
+                    });
+                }
+            }
+
+            result.entity_collisions = profile_section!(
+                self.frame_profile,
+                profiling::ENTITY_COLLISIONS,
+                self.handle_entity_collisions()
+            )?;
+
+            result.effect_hits = profile_section!(
+                self.frame_profile,
+                profiling::PENDING_EFFECTS,
+                self.process_pending_effects(dt)
+            )?;
+
+            if self.cull_dead {
+                let dead: Vec<Uuid> = self
+                    .entity_registry
+                    .iter()
+                    .filter(|e| e.inner_entity().get_health().is_dead())
+                    .map(|e| e.uuid())
+                    .collect();
+                for uuid in dead {
+                    if self.despawn_entity(uuid)?.is_some() {
+                        result.culled.push(uuid);
+                    }
+                }
+            }
+
+            Ok(result)
+        })
+    }
+
+    fn cell_index_of(&self, registered_entity: &E) -> Option<NodeIndex> {
+        self.field
+            .cell_at_pos(*registered_entity.inner_entity().get_box().pos())
+            .map(Cell::index)
+    }
+
+    #[cfg(feature = "profiling")]
+    /// The durations of each profiled section of the most recent entity tick and render
+    pub fn last_frame_profile(&self) -> &FrameProfile {
+        &self.frame_profile
     }
 
     // TODO! Find a way to pin the return type of this to a specific type within the registry
@@ -132,76 +531,184 @@ where
         self.entity_registry.player_mut()
     }
 
-    fn handle_entity_collisions(&mut self) -> ScarabResult<()> {
-        // This is kinda gross, but I don't really know how else to do it
-        // we'll see later how necessary it is to change
-        for this_index in 0..self.entity_registry.len() {
-            if let Some(this_one) = self.entity_registry.get_one(this_index) {
-                if !this_one.inner_entity().get_solidity().has_solidity() {
-                    continue;
+    fn handle_entity_collisions(&mut self) -> ScarabResult<Vec<EntityCollision>> {
+        let mut collisions = Vec::new();
+        let mut grid = EntityGrid::new(self.average_solid_entity_size());
+        // Each overlapping pair is resolved once, always pushing the entity with the higher index
+        // out of the earlier one along the edge with the least overlap
+        for later_index in 0..self.entity_registry.index_bound() {
+            let later_box = match self.entity_registry.get_one(later_index) {
+                Some(later) if later.inner_entity().get_solidity().has_solidity() => {
+                    *later.inner_entity().get_box()
                 }
+                // Pairs where either entity has no solidity are never resolved
+                _ => continue,
+            };
 
-                let this_one_box = *this_one.inner_entity().get_box();
-
-                for other_index in 0..this_index {
-                    if this_index == other_index {
-                        continue;
+            // Earlier entities are only ever moved while they're the later one in a pair,
+            // so everything already in the grid is where it will stay for this tick
+            let mut candidates = grid.nearby(&later_box);
+            let mut next = 0;
+            while let Some(&earlier_index) = candidates.get(next) {
+                next += 1;
+                let (earlier_box, earlier_solidity, earlier_uuid) = match (
+                    self.entity_registry.get_one(earlier_index),
+                    self.entity_registry.get_one(later_index),
+                ) {
+                    // Entities on layers the other doesn't collide with pass through each other
+                    (Some(earlier), Some(later))
+                        if later.inner_entity().collides_with(earlier.inner_entity()) =>
+                    {
+                        (
+                            *earlier.inner_entity().get_box(),
+                            *earlier.inner_entity().get_solidity(),
+                            earlier.uuid(),
+                        )
                     }
-                    if let Some(other_one) = self.entity_registry.get_one_mut(other_index) {
-                        if other_one.inner_entity().get_solidity().has_solidity() {
-                            other_one
-                                .inner_entity_mut()
-                                .get_box_mut()
-                                .shift_to_nonoverlapping(&this_one_box);
+                    _ => continue,
+                };
+
+                if let Some(later) = self.entity_registry.get_one_mut(later_index) {
+                    let later_solidity = *later.inner_entity().get_solidity();
+                    if let Some(edge) = later
+                        .inner_entity()
+                        .get_box()
+                        .min_penetration_edge(&earlier_box)
+                    {
+                        // Either entity letting the other through its touching edge means no collision
+                        if later_solidity.enter_edge(edge)
+                            || earlier_solidity.enter_edge(edge.opposite())
+                        {
+                            continue;
                         }
+
+                        later
+                            .inner_entity_mut()
+                            .get_box_mut()
+                            .set_touching_opposite_edge(&earlier_box, edge);
+                        collisions.push(EntityCollision {
+                            moved: later.uuid(),
+                            other: earlier_uuid,
+                        });
+
+                        // Being pushed may bring the entity near others it wasn't near before,
+                        // so look again for the rest of the earlier entities from where it is now
+                        let moved_box = *later.inner_entity().get_box();
+                        candidates = grid
+                            .nearby(&moved_box)
+                            .into_iter()
+                            .filter(|i| *i > earlier_index)
+                            .collect();
+                        next = 0;
                     }
                 }
             }
+
+            if let Some(later) = self.entity_registry.get_one(later_index) {
+                grid.insert(later_index, later.inner_entity().get_box());
+            }
         }
-        Ok(())
+        Ok(collisions)
     }
 
-    fn process_pending_effects(&mut self) -> ScarabResult<()> {
+    /// The average size of the solid entities, used to size the buckets of the collision broadphase
+    fn average_solid_entity_size(&self) -> f64 {
+        let (total_size, count) = self
+            .entity_registry
+            .iter()
+            .map(|e| e.inner_entity())
+            .filter(|e| e.get_solidity().has_solidity())
+            .fold((0.0, 0), |(total_size, count), e| {
+                let size = e.get_box().size();
+                (total_size + (size.w + size.h) / 2.0, count + 1)
+            });
+        if count == 0 {
+            1.0
+        } else {
+            total_size / count as f64
+        }
+    }
+
+    fn process_pending_effects(&mut self, dt: f64) -> ScarabResult<Vec<EffectHit>> {
+        let mut hits = Vec::new();
         let _ = self.pending_attacks.drain_filter(|effect| {
+            // A source that has been despawned no longer sources its effects,
+            // even if another entity has taken its index
+            let source = effect
+                .source
+                .filter(|s| self.entity_registry.get(s.handle).is_some());
+            if source.is_none() && effect.effect.needs_source() {
+                return true;
+            }
             let keep_effect = self
                 .entity_registry
-                .iter_mut()
-                .enumerate()
-                .filter_map(|(i, e)| {
+                .iter_handles_mut()
+                .filter_map(|(handle, e)| {
                     // TODO! remove inefficient retrieval of overlapping entities
                     // Do not attack if it's the source and the source can't be targeted
-                    if effect.source.map_or(true, |s| s.should_apply_effect(i))
-                        && e.inner_entity().get_box().has_overlap(&effect.target_area)
+                    if source.map_or(true, |s| s.should_apply_effect(handle))
+                        && effect.target_area.can_target(e)
                     {
-                        let res = effect.effect.apply_effect(e).ok();
-                        Some(res).flatten()
+                        let health_before = e.inner_entity().get_health().current();
+                        let res = effect.effect.apply_effect(e, dt).ok();
+                        if res.is_some() {
+                            hits.push(EffectHit {
+                                source: source.map(|s| s.handle),
+                                target: e.uuid(),
+                                damage: health_before - e.inner_entity().get_health().current(),
+                                target_box: *e.inner_entity().get_box(),
+                            });
+                        }
+                        res
                     } else {
                         None
                     }
                 })
-                .any(|b| b);
-
-            effect.source.map(|s| {
-                self.entity_registry
-                    .get_one_mut(s.index)
-                    .map(|source_entity| effect.effect.update_src(source_entity))
-                    .or_else(|| {
-                        println!(
-                            "error processing attack: could not find source entity: {:?}",
-                            effect
-                        );
-                        None
-                    });
-            });
+                // Every target needs the effect, so don't stop at the first that keeps it
+                .fold(false, |keep, b| keep | b);
+
+            if let Some(source_entity) = source.and_then(|s| self.entity_registry.get_mut(s.handle))
+            {
+                let _ = effect.effect.update_src(source_entity, &self.field);
+            }
+            effect.effect.end_tick(dt);
 
             // Drain filter *REMOVES* when true
             !keep_effect
         });
 
-        Ok(())
+        Ok(hits)
     }
 }
 
+/// A semver-like version of the scene's save format
+#[cfg(feature = "persistence")]
+pub const SCENE_SAVE_VERSION: &str = "0.2.0";
+
+#[cfg(feature = "persistence")]
+impl<E, V> Scene<E, V>
+where
+    E: Serialize + serde::de::DeserializeOwned,
+    V: Serialize + serde::de::DeserializeOwned,
+{
+    /// Saves the scene to `writer` behind a [SCENE_SAVE_VERSION] header
+    pub fn save_to_writer<W: std::io::Write>(&self, writer: W) -> ScarabResult<()> {
+        crate::persistence::write_versioned(writer, SCENE_SAVE_VERSION, self)
+    }
+
+    /// Loads a scene saved by [Scene::save_to_writer], failing if it was saved with a different [SCENE_SAVE_VERSION]
+    pub fn load_from_reader<R: std::io::Read>(reader: R) -> ScarabResult<Self> {
+        crate::persistence::read_versioned(reader, SCENE_SAVE_VERSION)
+    }
+}
+
+/// The random number generator owned by each [Scene]
+pub type SceneRng = rand_pcg::Pcg64;
+
+fn default_time_scale() -> f64 {
+    1.0
+}
+
 #[derive(Debug)]
 /// Various arguments used for running game ticks on entities
 pub struct GameTickArgs<'a, E> {
@@ -209,42 +716,154 @@ pub struct GameTickArgs<'a, E> {
     pub field: &'a Field,
     /// The current attacks waiting to be processed in the game loop. Add to this to attack another entity
     pub pending_attacks: &'a mut Vec<PendingEffect<E>>,
+    /// Entities to add to the scene once every entity has ticked. Add to this to spawn an entity (i.e. a projectile).
+    /// They may reuse the index of a despawned entity, but never change the indices of existing entities
+    pub spawn_queue: &'a mut Vec<E>,
     /// The change in time for this update
     pub dt: f64,
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Everything of note that happened during a single [Scene::step].
+/// Trigger events are not reported yet
+pub struct StepResult {
+    /// Entities whose position moved into a different cell
+    pub cell_transitions: Vec<CellTransition>,
+    /// Pairs of solid entities that had to be pushed apart
+    pub entity_collisions: Vec<EntityCollision>,
+    /// Entities that a pending effect was applied to
+    pub effect_hits: Vec<EffectHit>,
+    /// Entities that were despawned for running out of health (see [Scene::set_cull_dead])
+    pub culled: Vec<Uuid>,
+    /// Entities that were spawned from the [spawn queue](GameTickArgs::spawn_queue)
+    pub spawned: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An entity's position moving from one cell to another
+pub struct CellTransition {
+    /// The entity that moved
+    pub entity: Uuid,
+    /// The cell the entity was in before the step, if any
+    pub from: Option<NodeIndex>,
+    /// The cell the entity is in after the step, if any
+    pub to: Option<NodeIndex>,
+}
+
+/// A uniform grid of buckets holding the indices of the entities that overlap them,
+/// so that collisions are only checked between entities that are near each other
+struct EntityGrid {
+    /// The width and height of each bucket
+    bucket_size: f64,
+    buckets: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl EntityGrid {
+    fn new(bucket_size: f64) -> Self {
+        Self {
+            bucket_size,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// The keys of every bucket the box overlaps
+    fn buckets_of(&self, physbox: &PhysBox) -> impl Iterator<Item = (i64, i64)> {
+        let (left, top) = self.bucket_of(*physbox.pos());
+        let (right, bottom) = self.bucket_of([physbox.right_x(), physbox.bottom_y()].into());
+        (left..=right).flat_map(move |x| (top..=bottom).map(move |y| (x, y)))
+    }
+
+    fn bucket_of(&self, pos: Point) -> (i64, i64) {
+        (
+            (pos.x / self.bucket_size).floor() as i64,
+            (pos.y / self.bucket_size).floor() as i64,
+        )
+    }
+
+    /// Entities must be inserted in index order to keep each bucket sorted
+    fn insert(&mut self, i: usize, physbox: &PhysBox) {
+        for key in self.buckets_of(physbox) {
+            self.buckets.entry(key).or_default().push(i);
+        }
+    }
+
+    /// The entities that might overlap the given box, in index order
+    fn nearby(&self, physbox: &PhysBox) -> Vec<usize> {
+        let mut candidates: Vec<usize> = self
+            .buckets_of(physbox)
+            .filter_map(|key| self.buckets.get(&key))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Two solid entities that overlapped after moving
+pub struct EntityCollision {
+    /// The entity that was shifted out of the overlap
+    pub moved: Uuid,
+    /// The entity it was overlapping
+    pub other: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A pending effect being applied to an entity
+pub struct EffectHit {
+    /// The handle of the effect's source, if it had one that's still registered
+    pub source: Option<EntityHandle>,
+    /// The entity the effect was applied to
+    pub target: Uuid,
+    /// How much health the target lost to the effect (negative if it was healed)
+    pub damage: f64,
+    /// The target's box just after the effect was applied
+    pub target_box: PhysBox,
+}
+
+#[derive(Debug)]
+/// Arguments for an entity's [spawn](RegisteredEntity::on_spawn) and [despawn](RegisteredEntity::on_despawn) hooks
+pub struct SpawnArgs<'a> {
+    /// The field which the entity is on
+    pub field: &'a Field,
+    /// The entity's handle in the scene. When despawning, this is the handle it had just before removal
+    pub handle: EntityHandle,
+}
+
 #[derive(Debug)]
 /// An effect on other entities that the scene should process on the next game tick
 pub struct PendingEffect<E> {
     /// An optional source of the effect
     pub source: Option<EffectSource>,
-    /// The attack's target area
-    /// TODO: this could be changed into a more generalized "EffectTarget" which could just
-    /// get the nearest "n" entities within a range for example
-    pub target_area: PhysBox,
+    /// Selects the entities the effect applies to
+    pub target_area: Box<dyn EffectTarget<E>>,
     /// Handles the logic of applying the effect
     pub effect: Box<dyn TargetsOthers<E>>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-/// A source of an effect
+/// A source of an effect.
+/// Once the source is [despawned](Scene::despawn_entity) its handle stops resolving,
+/// and the effect is processed as if it had no source
 pub struct EffectSource {
-    /// The source's registry index
-    pub index: usize,
+    /// The source's handle in the scene
+    pub handle: EntityHandle,
     /// Whether or not the effect should target the source
     pub can_target_source: bool,
 }
 
 impl EffectSource {
-    fn should_apply_effect(&self, target_index: usize) -> bool {
-        !(!self.can_target_source && target_index == self.index)
+    fn should_apply_effect(&self, target: EntityHandle) -> bool {
+        !(!self.can_target_source && target == self.handle)
     }
 }
 
-impl From<(usize, bool)> for EffectSource {
-    fn from((index, can_target_source): (usize, bool)) -> Self {
+impl From<(EntityHandle, bool)> for EffectSource {
+    fn from((handle, can_target_source): (EntityHandle, bool)) -> Self {
         Self {
-            index,
+            handle,
             can_target_source,
         }
     }
@@ -253,37 +872,909 @@ impl From<(usize, bool)> for EffectSource {
 /// Effects that can target other entities of type `E`
 pub trait TargetsOthers<E>: Debug {
     /// Apply the main effect to a target entity (i.e. do damage, apply status effects, etc.)
+    /// `dt` is the change in time for this tick, for effects that last over several ticks.
     /// Returns whether or not the effect needs to process on the next tick
-    fn apply_effect(&mut self, target: &mut E) -> ScarabResult<bool>;
+    fn apply_effect(&mut self, target: &mut E, dt: f64) -> ScarabResult<bool>;
 
     /// Apply any necessary updates to the source of the effect
-    /// This could be animation states, draining energy or any other necessary effect
-    fn update_src(&mut self, src: &mut E) -> ScarabResult<()>;
+    /// This could be animation states, draining energy or any other necessary effect.
+    /// `field` is the scene's field, for effects that move the source around it
+    fn update_src(&mut self, src: &mut E, field: &Field) -> ScarabResult<()>;
+
+    /// Runs once per tick after the effect has been applied to all of its targets,
+    /// i.e. to count down a duration shared by every target. Does nothing by default
+    fn end_tick(&mut self, _dt: f64) {}
+
+    /// Whether the effect only makes sense while its source is registered, i.e. because it
+    /// acts on the source. Such effects are dropped once the source is gone instead of
+    /// applying without one. False by default
+    fn needs_source(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        gameobject::{
+            entity::{
+                effect_helpers::{DamageOverTime, PathFollow},
+                Entity, EntityDefaults,
+            },
+            field::FieldColorView,
+            NO_SOLIDITY,
+        },
+        test_utils::{create_seeded_test_scene, create_test_field, create_test_scene, TestEntity},
+        types::HasUuid,
+        ScarabError,
+    };
+
+    #[derive(Debug)]
+    struct Damage(f64);
+
+    impl TargetsOthers<TestEntity> for Damage {
+        fn apply_effect(&mut self, target: &mut TestEntity, _dt: f64) -> ScarabResult<bool> {
+            target.entity.get_health_mut().raw_damage(self.0);
+            Ok(false)
+        }
+
+        fn update_src(&mut self, _src: &mut TestEntity, _field: &Field) -> ScarabResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registered_handle_gets_and_targets_entity() {
+        let mut scene = create_test_scene();
+        let first = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(TestEntity::at([50.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        assert_ne!(first, second);
+
+        let target = scene.get_entity(second).unwrap();
+        assert_eq!(target.entity.uuid(), second.uuid);
+        let target_area = *target.entity.get_box();
+
+        scene.pending_attacks.push(PendingEffect {
+            source: Some((first, false).into()),
+            target_area: Box::new(target_area),
+            effect: Box::new(Damage(3.0)),
+        });
+        scene.process_pending_effects(0.1).unwrap();
+
+        let full_health = scene.get_entity(first).unwrap().entity.get_health().max();
+        assert_eq!(
+            scene
+                .get_entity(first)
+                .unwrap()
+                .entity
+                .get_health()
+                .current(),
+            full_health
+        );
+        assert_eq!(
+            scene
+                .get_entity(second)
+                .unwrap()
+                .entity
+                .get_health()
+                .current(),
+            full_health - 3.0
+        );
+    }
+
+    #[test]
+    fn lasting_effect_applies_to_every_target_in_area() {
+        let mut scene = create_test_scene();
+        let targets: Vec<EntityHandle> = [[10.0, 10.0, 5.0, 5.0], [30.0, 10.0, 5.0, 5.0]]
+            .into_iter()
+            .map(|physbox| scene.register_entity(TestEntity::at(physbox)).unwrap())
+            .collect();
+        scene.pending_attacks.push(PendingEffect {
+            source: None,
+            target_area: Box::new(PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap()),
+            effect: Box::new(DamageOverTime::new(4.0, 1.0)),
+        });
+        let damage_taken = |scene: &Scene<TestEntity, _>, target: EntityHandle| {
+            let health = scene.get_entity(target).unwrap().entity.get_health();
+            health.max() - health.current()
+        };
+
+        let hits = scene.process_pending_effects(0.5).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(scene.pending_attacks.len(), 1);
+        for &target in &targets {
+            assert_eq!(damage_taken(&scene, target), 2.0);
+        }
+
+        // The duration is shared by both targets, so it runs out after the second tick
+        let hits = scene.process_pending_effects(0.5).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(scene.pending_attacks.is_empty());
+        for &target in &targets {
+            assert_eq!(damage_taken(&scene, target), 4.0);
+        }
+    }
+
+    #[test]
+    fn source_dependent_effect_is_dropped_with_its_source() {
+        let mut scene = create_test_scene();
+        let follower = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let target = scene
+            .register_entity(TestEntity::at([30.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene.pending_attacks.push(
+            PathFollow::new(5.0)
+                .into_pending_effect(follower, PhysBox::new([25.0, 5.0, 15.0, 15.0]).unwrap()),
+        );
+
+        scene.process_pending_effects(0.1).unwrap();
+        assert_eq!(scene.pending_attacks.len(), 1);
+
+        scene.despawn_entity(follower.uuid).unwrap().unwrap();
+        let hits = scene.process_pending_effects(0.1).unwrap();
+        assert!(hits.is_empty());
+        assert!(scene.pending_attacks.is_empty());
+        assert!(scene.get_entity(target).is_some());
+    }
+
+    #[test]
+    fn step_reports_transitions_collisions_and_hits() {
+        let mut scene = create_test_scene();
+        let field = create_test_field();
+        let left = field.cell_at_pos([10.0, 10.0].into()).unwrap().index();
+        let right = field.cell_at_pos([60.0, 10.0].into()).unwrap().index();
+
+        let mut walker = TestEntity::at([45.0, 10.0, 4.0, 4.0]);
+        walker.entity.set_max_velocity(100.0).unwrap();
+        walker.entity.set_velocity([100.0, 0.0].into());
+        let walker = scene.register_entity(walker).unwrap();
+        let first = scene
+            .register_entity(TestEntity::at([10.0, 50.0, 10.0, 10.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(TestEntity::at([15.0, 50.0, 10.0, 10.0]))
+            .unwrap();
+
+        scene.pending_attacks.push(PendingEffect {
+            source: None,
+            target_area: Box::new(PhysBox::new([0.0, 40.0, 40.0, 30.0]).unwrap()),
+            effect: Box::new(Damage(1.0)),
+        });
+        let result = scene.step(0.1).unwrap();
+
+        assert_eq!(
+            result.cell_transitions,
+            vec![CellTransition {
+                entity: walker.uuid,
+                from: Some(left),
+                to: Some(right),
+            }]
+        );
+        assert_eq!(
+            result.entity_collisions,
+            vec![EntityCollision {
+                moved: second.uuid,
+                other: first.uuid,
+            }]
+        );
+        assert_eq!(result.effect_hits.len(), 2);
+        for target in [first, second] {
+            let hit = result
+                .effect_hits
+                .iter()
+                .find(|h| h.target == target.uuid)
+                .unwrap();
+            assert_eq!(hit.source, None);
+            assert_eq!(hit.damage, 1.0);
+            assert_eq!(
+                hit.target_box,
+                *scene.get_entity(target).unwrap().entity.get_box()
+            );
+        }
+    }
+
+    fn test_handle(index: usize, generation: u32) -> EntityHandle {
+        EntityHandle {
+            index,
+            generation,
+            uuid: Uuid::new_v4(),
+        }
+    }
 
     #[test]
     fn effect_source_always_targets_when_not_source() {
-        let source_index = 0;
-        let mut source: EffectSource = (source_index, false).into();
+        let source_handle = test_handle(0, 0);
+        let mut source: EffectSource = (source_handle, false).into();
 
-        assert!(source.should_apply_effect(source_index + 1));
+        assert!(source.should_apply_effect(test_handle(1, 0)));
+        // An entity that reused the source's index isn't the source
+        assert!(source.should_apply_effect(test_handle(0, 1)));
 
         source.can_target_source = true;
-        assert!(source.should_apply_effect(source_index + 1));
+        assert!(source.should_apply_effect(test_handle(1, 0)));
     }
 
     #[test]
     fn effect_source_targets_source_only_when_able() {
-        let source_index = 0;
-        let mut source: EffectSource = (source_index, false).into();
+        let source_handle = test_handle(0, 0);
+        let mut source: EffectSource = (source_handle, false).into();
 
-        assert!(!source.should_apply_effect(source_index));
+        assert!(!source.should_apply_effect(source_handle));
 
         source.can_target_source = true;
-        assert!(source.should_apply_effect(source_index));
+        assert!(source.should_apply_effect(source_handle));
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn tick_records_profile_sections() {
+        let mut scene = create_test_scene();
+        scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene.tick_entities(0.1).unwrap();
+
+        let profile = scene.last_frame_profile();
+        for section in [
+            profiling::TICK_ENTITIES,
+            profiling::ENTITY_TICKS,
+            profiling::ENTITY_COLLISIONS,
+            profiling::PENDING_EFFECTS,
+        ] {
+            let duration = profile.get(section).unwrap();
+            assert!(duration.as_secs_f64() >= 0.0);
+        }
+        assert!(profile.get(profiling::RENDER).is_none());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn tick_reports_non_finite_entity_state() {
+        let mut scene = create_test_scene();
+        let handle = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene
+            .get_entity_mut(handle)
+            .unwrap()
+            .entity
+            .get_box_mut()
+            .set_pos([f64::NAN, 10.0].into());
+
+        assert!(matches!(
+            scene.tick_entities(0.1),
+            Err(ScarabError::PhysicsError(PhysicsError::NonFiniteState(uuid))) if uuid == handle.uuid
+        ));
+    }
+
+    #[test]
+    fn solid_entities_separate() {
+        let mut scene = create_test_scene();
+        let first = scene
+            .register_entity(TestEntity::at([10.0, 50.0, 10.0, 10.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(TestEntity::at([17.0, 52.0, 10.0, 10.0]))
+            .unwrap();
+
+        let collisions = scene.handle_entity_collisions().unwrap();
+        assert_eq!(
+            collisions,
+            vec![EntityCollision {
+                moved: second.uuid,
+                other: first.uuid,
+            }]
+        );
+
+        // The later entity is pushed right, the axis with the least overlap
+        let first_box = *scene.get_entity(first).unwrap().entity.get_box();
+        let second_box = *scene.get_entity(second).unwrap().entity.get_box();
+        assert_eq!(first_box, PhysBox::new([10.0, 50.0, 10.0, 10.0]).unwrap());
+        assert_eq!(second_box, PhysBox::new([20.0, 52.0, 10.0, 10.0]).unwrap());
+        assert!(!first_box.has_overlap(&second_box));
+    }
+
+    #[test]
+    fn passable_entity_doesnt_collide() {
+        let mut scene = create_test_scene();
+        let solid = scene
+            .register_entity(TestEntity::at([10.0, 50.0, 10.0, 10.0]))
+            .unwrap();
+        let mut projectile = TestEntity::at([17.0, 52.0, 4.0, 4.0]);
+        projectile.entity = Entity::with_defaults(EntityDefaults {
+            solidity: NO_SOLIDITY,
+            ..Default::default()
+        })
+        .unwrap();
+        projectile.entity.get_box_mut().set_pos([17.0, 52.0].into());
+        let projectile = scene.register_entity(projectile).unwrap();
+
+        assert!(scene.handle_entity_collisions().unwrap().is_empty());
+        assert_eq!(
+            *scene.get_entity(projectile).unwrap().entity.get_box().pos(),
+            [17.0, 52.0].into()
+        );
+        assert_eq!(
+            *scene.get_entity(solid).unwrap().entity.get_box().pos(),
+            [10.0, 50.0].into()
+        );
+    }
+
+    #[test]
+    fn non_interacting_layers_pass_through() {
+        const PLAYER: u32 = 0b01;
+        const ENEMY: u32 = 0b10;
+        let enemy = |physbox| {
+            let mut e = TestEntity::at(physbox);
+            e.entity.set_collision_layer(ENEMY);
+            e.entity.set_collision_mask(PLAYER);
+            e
+        };
+
+        let mut scene = create_test_scene();
+        let first = scene
+            .register_entity(enemy([10.0, 50.0, 10.0, 10.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(enemy([17.0, 52.0, 10.0, 10.0]))
+            .unwrap();
+        assert!(scene.handle_entity_collisions().unwrap().is_empty());
+        assert_eq!(
+            *scene.get_entity(second).unwrap().entity.get_box().pos(),
+            [17.0, 52.0].into()
+        );
+
+        // The default entity is on every layer and collides with every layer
+        let mut player = TestEntity::at([12.0, 48.0, 10.0, 10.0]);
+        player.entity.set_collision_layer(PLAYER);
+        let player = scene.register_entity(player).unwrap();
+        let collisions = scene.handle_entity_collisions().unwrap();
+        assert!(collisions.contains(&EntityCollision {
+            moved: player.uuid,
+            other: first.uuid,
+        }));
+        assert!(!collisions
+            .iter()
+            .any(|c| c.moved == second.uuid || c.moved == first.uuid));
+    }
+
+    /// The pairwise collision handling the broadphase has to agree with
+    fn brute_force_collisions(
+        scene: &mut Scene<TestEntity, FieldColorView>,
+    ) -> Vec<EntityCollision> {
+        let mut collisions = Vec::new();
+        for later_index in 0..scene.entity_registry.index_bound() {
+            for earlier_index in 0..later_index {
+                let earlier = scene.entity_registry.get_one(earlier_index).unwrap();
+                let collides = scene
+                    .entity_registry
+                    .get_one(later_index)
+                    .unwrap()
+                    .entity
+                    .collides_with(&earlier.entity);
+                let (earlier_box, earlier_solidity, earlier_uuid) = (
+                    *earlier.entity.get_box(),
+                    *earlier.entity.get_solidity(),
+                    earlier.uuid(),
+                );
+                let later = scene.entity_registry.get_one_mut(later_index).unwrap();
+                let later_solidity = *later.entity.get_solidity();
+                if !later_solidity.has_solidity() || !earlier_solidity.has_solidity() || !collides {
+                    continue;
+                }
+                if let Some(edge) = later.entity.get_box().min_penetration_edge(&earlier_box) {
+                    if later_solidity.enter_edge(edge)
+                        || earlier_solidity.enter_edge(edge.opposite())
+                    {
+                        continue;
+                    }
+                    later
+                        .entity
+                        .get_box_mut()
+                        .set_touching_opposite_edge(&earlier_box, edge);
+                    collisions.push(EntityCollision {
+                        moved: later.uuid(),
+                        other: earlier_uuid,
+                    });
+                }
+            }
+        }
+        collisions
+    }
+
+    #[test]
+    fn broadphase_only_resolves_overlapping_pair() {
+        let mut scene = create_test_scene();
+        for i in 0..1000 {
+            let (x, y) = ((i % 40) as f64 * 20.0, (i / 40) as f64 * 20.0);
+            scene
+                .register_entity(TestEntity::at([x, y, 10.0, 10.0]))
+                .unwrap();
+        }
+        let first = scene
+            .register_entity(TestEntity::at([1000.0, 1000.0, 10.0, 10.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(TestEntity::at([1005.0, 1002.0, 10.0, 10.0]))
+            .unwrap();
+
+        // The grid only offers a handful of the ~500k possible pairs as candidates
+        let mut grid = EntityGrid::new(scene.average_solid_entity_size());
+        let boxes: Vec<PhysBox> = scene
+            .entity_registry
+            .iter()
+            .map(|e| *e.entity.get_box())
+            .collect();
+        for (i, physbox) in boxes.iter().enumerate() {
+            grid.insert(i, physbox);
+        }
+        let candidate_pairs: usize = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, physbox)| grid.nearby(physbox).into_iter().filter(|j| *j > i).count())
+            .sum();
+        assert!(candidate_pairs < boxes.len());
+
+        let collisions = scene.handle_entity_collisions().unwrap();
+        assert_eq!(
+            collisions,
+            vec![EntityCollision {
+                moved: second.uuid,
+                other: first.uuid,
+            }]
+        );
+    }
+
+    #[test]
+    fn broadphase_matches_brute_force() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(320);
+        let mut scene = create_test_scene();
+        let mut brute_scene = create_test_scene();
+        for _ in 0..200 {
+            let physbox = [
+                rng.gen_range(0.0..200.0),
+                rng.gen_range(0.0..200.0),
+                rng.gen_range(2.0..30.0),
+                rng.gen_range(2.0..30.0),
+            ];
+            scene.register_entity(TestEntity::at(physbox)).unwrap();
+            brute_scene
+                .register_entity(TestEntity::at(physbox))
+                .unwrap();
+        }
+
+        // Each scene's entities have their own uuids, so compare collisions by index
+        let as_indices = |scene: &Scene<TestEntity, FieldColorView>, c: &EntityCollision| {
+            let index_of = |uuid| scene.entity_registry.index_of_uuid(uuid);
+            (index_of(c.moved), index_of(c.other))
+        };
+        let collisions: Vec<_> = scene
+            .handle_entity_collisions()
+            .unwrap()
+            .iter()
+            .map(|c| as_indices(&scene, c))
+            .collect();
+        let brute_collisions: Vec<_> = brute_force_collisions(&mut brute_scene)
+            .iter()
+            .map(|c| as_indices(&brute_scene, c))
+            .collect();
+        assert!(!collisions.is_empty());
+        assert_eq!(collisions, brute_collisions);
+        for (e, brute) in scene
+            .entity_registry
+            .iter()
+            .zip(brute_scene.entity_registry.iter())
+        {
+            assert_eq!(e.entity.get_box(), brute.entity.get_box());
+        }
+    }
+
+    #[test]
+    fn raycast_entities_hits_closest() {
+        let mut scene = create_test_scene();
+        for physbox in [
+            [60.0, 8.0, 5.0, 5.0],
+            [40.0, 8.0, 5.0, 5.0],
+            [20.0, 30.0, 5.0, 5.0],
+        ] {
+            scene.register_entity(TestEntity::at(physbox)).unwrap();
+        }
+        let mut passable = TestEntity::at([0.0, 0.0, 1.0, 1.0]);
+        passable.entity = Entity::with_defaults(EntityDefaults {
+            solidity: NO_SOLIDITY,
+            ..Default::default()
+        })
+        .unwrap();
+        passable.entity.get_box_mut().set_pos([30.0, 8.0].into());
+        passable
+            .entity
+            .get_box_mut()
+            .set_size([5.0, 5.0].into())
+            .unwrap();
+        scene.register_entity(passable).unwrap();
+
+        // The passable entity at index 3 is skipped
+        assert_eq!(
+            scene.raycast_entities([0.0, 10.0].into(), [2.0, 0.0].into(), 100.0),
+            Some((1, [40.0, 10.0].into()))
+        );
+        assert_eq!(
+            scene.raycast_entities_filtered(
+                [0.0, 10.0].into(),
+                [1.0, 0.0].into(),
+                100.0,
+                |i, _| i != 1
+            ),
+            Some((3, [30.0, 10.0].into()))
+        );
+        // Too short, and pointed the wrong way
+        assert_eq!(
+            scene.raycast_entities([0.0, 10.0].into(), [1.0, 0.0].into(), 35.0),
+            None
+        );
+        assert_eq!(
+            scene.raycast_entities([0.0, 10.0].into(), [-1.0, 0.0].into(), 100.0),
+            None
+        );
+    }
+
+    #[test]
+    fn render_stats_count_culled_entities() {
+        let mut scene = create_test_scene();
+        for physbox in [
+            [10.0, 10.0, 5.0, 5.0],
+            [48.0, 48.0, 5.0, 5.0],
+            [200.0, 10.0, 5.0, 5.0],
+            [10.0, 500.0, 5.0, 5.0],
+            [-100.0, -100.0, 5.0, 5.0],
+        ] {
+            scene.register_entity(TestEntity::at(physbox)).unwrap();
+        }
+        let camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 50.0, 50.0]).unwrap(),
+            [100.0, 100.0],
+        );
+
+        assert_eq!(
+            scene.render_stats(&camera),
+            RenderStats {
+                drawn: 2,
+                culled: 3
+            }
+        );
+    }
+
+    #[test]
+    fn entities_render_in_layer_order() {
+        let mut scene = create_test_scene();
+        for layer in [1, -1, 0, 1, -1] {
+            let mut entity = TestEntity::at([10.0, 10.0, 5.0, 5.0]);
+            entity.layer = layer;
+            scene.register_entity(entity).unwrap();
+        }
+
+        assert_eq!(scene.render_order(), vec![1, 4, 2, 0, 3]);
+    }
+
+    #[test]
+    fn ticking_entity_spawns_into_scene() {
+        let mut scene = create_test_scene();
+        let mut spawner = TestEntity::at([10.0, 10.0, 5.0, 5.0]);
+        let projectile = TestEntity::at([20.0, 10.0, 2.0, 2.0]);
+        let projectile_uuid = projectile.uuid();
+        spawner.to_spawn.push(projectile);
+        scene.register_entity(spawner).unwrap();
+
+        let result = scene.step(0.1).unwrap();
+        assert_eq!(result.spawned, vec![projectile_uuid]);
+        assert_eq!(scene.entity_registry.len(), 2);
+        let spawned = scene.entity_registry.get_one(1).unwrap();
+        assert_eq!(spawned.uuid(), projectile_uuid);
+        assert_eq!(spawned.spawned, 1);
+
+        assert!(scene.step(0.1).unwrap().spawned.is_empty());
+        assert_eq!(scene.entity_registry.len(), 2);
+    }
+
+    #[test]
+    fn entities_in_box_selects_overlapping() {
+        let mut scene = create_test_scene();
+        let handles: Vec<EntityHandle> = [
+            [5.0, 5.0, 5.0, 5.0],
+            [20.0, 20.0, 5.0, 5.0],
+            [45.0, 45.0, 10.0, 10.0],
+            [70.0, 10.0, 5.0, 5.0],
+            [80.0, 80.0, 5.0, 5.0],
+        ]
+        .into_iter()
+        .map(|physbox| scene.register_entity(TestEntity::at(physbox)).unwrap())
+        .collect();
+        // Leaves a hole before the overlapping entities
+        scene.despawn_entity(handles[0].uuid).unwrap().unwrap();
+        let area = PhysBox::new([15.0, 15.0, 35.0, 35.0]).unwrap();
+
+        assert_eq!(scene.entities_in_box(&area), vec![1, 2]);
+        assert!(scene
+            .entities_in_box(&PhysBox::new([0.0, 60.0, 10.0, 10.0]).unwrap())
+            .is_empty());
+
+        let mut hit = Vec::new();
+        for (i, entity) in scene.entities_in_box_mut(&area) {
+            entity.entity.get_health_mut().raw_damage(1.0);
+            hit.push(i);
+        }
+        assert_eq!(hit, vec![1, 2]);
+        let damaged: Vec<usize> = scene
+            .entity_registry
+            .iter_indexed()
+            .filter(|(_, e)| e.entity.get_health().current() < e.entity.get_health().max())
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(damaged, vec![1, 2]);
+    }
+
+    #[test]
+    fn update_runs_whole_fixed_steps() {
+        let mut scene = create_test_scene();
+        let mut walker = TestEntity::at([10.0, 10.0, 2.0, 2.0]);
+        walker.entity.set_max_velocity(10.0).unwrap();
+        walker.entity.set_velocity([4.0, 0.0].into());
+        let walker = scene.register_entity(walker).unwrap();
+
+        let fixed_dt = 0.25;
+        let mut steps = Vec::new();
+        for frame_dt in [0.375, 0.125, 0.625, 0.0625] {
+            steps.push(scene.update(frame_dt, fixed_dt).unwrap());
+        }
+
+        assert_eq!(steps, vec![1, 1, 2, 0]);
+        assert_eq!(scene.fixed_step_count(), 4);
+        let simulated = steps.iter().sum::<usize>() as f64 * fixed_dt;
+        assert_eq!(simulated, 1.0);
+        assert_eq!(scene.accumulated_time(), 0.1875);
+        assert_eq!(scene.interpolation_alpha(fixed_dt), 0.75);
+        assert_eq!(
+            scene.get_entity(walker).unwrap().entity.get_box().pos().x,
+            10.0 + 4.0 * simulated
+        );
+
+        assert!(matches!(
+            scene.update(0.1, 0.0),
+            Err(ScarabError::PhysicsError(PhysicsError::FixedTimestep))
+        ));
+    }
+
+    #[test]
+    fn time_scale_and_pause_affect_movement() {
+        let mut scene = create_test_scene();
+        let mut walker = TestEntity::at([10.0, 10.0, 2.0, 2.0]);
+        walker.entity.set_max_velocity(10.0).unwrap();
+        walker.entity.set_velocity([4.0, 0.0].into());
+        let walker = scene.register_entity(walker).unwrap();
+        let x = |scene: &Scene<TestEntity, _>| {
+            scene.get_entity(walker).unwrap().entity.get_box().pos().x
+        };
+
+        scene.tick_entities(1.0).unwrap();
+        assert_eq!(x(&scene), 14.0);
+
+        scene.set_time_scale(0.5).unwrap();
+        scene.tick_entities(1.0).unwrap();
+        assert_eq!(x(&scene), 16.0);
+
+        scene.set_paused(true);
+        scene.tick_entities(1.0).unwrap();
+        assert_eq!(x(&scene), 16.0);
+
+        scene.set_paused(false);
+        scene.tick_entities(1.0).unwrap();
+        assert_eq!(x(&scene), 18.0);
+
+        for invalid in [-1.0, 0.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(scene.set_time_scale(invalid), Err(PhysicsError::TimeScale));
+        }
+        assert_eq!(scene.time_scale(), 0.5);
+    }
+
+    #[test]
+    fn step_culls_dead_entities() {
+        let mut scene = create_test_scene();
+        let first = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let middle = scene
+            .register_entity(TestEntity::at([30.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let last = scene
+            .register_entity(TestEntity::at([60.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+
+        let health = scene
+            .get_entity_mut(middle)
+            .unwrap()
+            .entity
+            .get_health_mut();
+        let max = health.max();
+        health.raw_damage(max);
+
+        // Dead entities stay registered unless culling is turned on
+        let result = scene.step(0.1).unwrap();
+        assert!(result.culled.is_empty());
+        assert!(scene.get_entity(middle).is_some());
+
+        scene.set_cull_dead(true);
+        let result = scene.step(0.1).unwrap();
+        assert_eq!(result.culled, vec![middle.uuid]);
+
+        let remaining: Vec<Uuid> = scene.entity_registry.iter().map(|e| e.uuid()).collect();
+        assert_eq!(remaining, vec![first.uuid, last.uuid]);
+        assert!(scene.get_entity(first).is_some());
+        assert!(scene.despawn_entity(middle.uuid).unwrap().is_none());
+    }
+
+    #[test]
+    fn spawn_and_despawn_hooks_run() {
+        let mut scene = create_test_scene();
+        let first = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let second = scene
+            .register_entity(TestEntity::at([50.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+
+        assert_eq!(scene.get_entity(first).unwrap().spawned, 1);
+        assert_eq!(scene.get_entity(first).unwrap().despawned, 0);
+
+        let despawned = scene.despawn_entity(first.uuid).unwrap().unwrap();
+        assert_eq!(despawned.spawned, 1);
+        assert_eq!(despawned.despawned, 1);
+        assert!(scene.despawn_entity(first.uuid).unwrap().is_none());
+
+        // The second entity keeps its index
+        assert_eq!(scene.get_entity(second).unwrap().spawned, 1);
+        assert_eq!(scene.get_entity(second).unwrap().despawned, 0);
+    }
+
+    #[test]
+    fn failed_spawn_hook_unregisters_entity() {
+        let mut scene = create_test_scene();
+        let mut failing = TestEntity::at([10.0, 10.0, 5.0, 5.0]);
+        failing.fail_hooks = true;
+        let uuid = failing.uuid();
+
+        assert!(scene.register_entity(failing).is_err());
+        assert_eq!(scene.entity_registry.len(), 0);
+        assert!(scene.entity_registry.index_of_uuid(uuid).is_none());
+
+        // Its index is free for the next entity
+        let next = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        assert_eq!(scene.entity_registry.len(), 1);
+        assert_eq!(scene.get_entity(next).unwrap().spawned, 1);
+    }
+
+    #[test]
+    fn failed_despawn_hook_keeps_entity_registered() {
+        let mut scene = create_test_scene();
+        let handle = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene.get_entity_mut(handle).unwrap().fail_hooks = true;
+
+        assert!(scene.despawn_entity(handle.uuid).is_err());
+        let entity = scene.get_entity(handle).unwrap();
+        assert_eq!(entity.despawned, 1);
+
+        // Once the hook succeeds the despawn goes through
+        scene.get_entity_mut(handle).unwrap().fail_hooks = false;
+        let despawned = scene.despawn_entity(handle.uuid).unwrap().unwrap();
+        assert_eq!(despawned.despawned, 2);
+        assert!(scene.get_entity(handle).is_none());
+    }
+
+    #[test]
+    fn despawned_source_no_longer_sources_its_effects() {
+        let mut scene = create_test_scene();
+        let source = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        let target = scene
+            .register_entity(TestEntity::at([50.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene.pending_attacks.push(PendingEffect {
+            source: Some((source, false).into()),
+            target_area: Box::new(PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap()),
+            effect: Box::new(Damage(3.0)),
+        });
+
+        scene.despawn_entity(source.uuid).unwrap().unwrap();
+
+        // The new entity takes the source's old index, but isn't treated as the source
+        let replacement = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        assert_eq!(replacement.index, source.index);
+        assert!(scene.get_entity(source).is_none());
+        let hits = scene.process_pending_effects(0.1).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|h| h.source.is_none()));
+        assert_eq!(
+            scene
+                .get_entity(target)
+                .unwrap()
+                .entity
+                .get_health()
+                .current(),
+            7.0
+        );
+    }
+
+    #[test]
+    fn spawn_hook_runs_once_per_registration() {
+        let mut scene = create_test_scene();
+        let handle = scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene.step(0.1).unwrap();
+        assert_eq!(scene.get_entity(handle).unwrap().spawned, 1);
+
+        // Registering the same entity again after despawning it counts as a new spawn
+        let despawned = scene.despawn_entity(handle.uuid).unwrap().unwrap();
+        let handle = scene.register_entity(despawned).unwrap();
+        let entity = scene.get_entity(handle).unwrap();
+        assert_eq!(entity.spawned, 2);
+        assert_eq!(entity.despawned, 1);
+    }
+
+    #[test]
+    fn seeded_scenes_draw_the_same_values() {
+        use rand::Rng;
+
+        let mut first = create_seeded_test_scene(326);
+        let mut second = create_seeded_test_scene(326);
+        let draws = |scene: &mut Scene<TestEntity, FieldColorView>| -> Vec<u64> {
+            (0..10).map(|_| scene.rng_mut().gen()).collect()
+        };
+        assert_eq!(draws(&mut first), draws(&mut second));
+
+        // A saved scene continues from where its generator left off
+        let serialized = serde_json::to_string(&first).unwrap();
+        let mut loaded: Scene<TestEntity, FieldColorView> =
+            serde_json::from_str(&serialized).unwrap();
+        assert_eq!(draws(&mut loaded), draws(&mut first));
+        assert_ne!(draws(&mut first), draws(&mut create_seeded_test_scene(327)));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn scene_round_trips_through_save() {
+        let mut scene = create_test_scene();
+        scene
+            .register_entity(TestEntity::at([10.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+        scene
+            .register_entity(TestEntity::at([50.0, 10.0, 5.0, 5.0]))
+            .unwrap();
+
+        let mut buf = Vec::new();
+        scene.save_to_writer(&mut buf).unwrap();
+        let loaded: Scene<TestEntity, FieldColorView> =
+            Scene::load_from_reader(buf.as_slice()).unwrap();
+
+        assert_eq!(loaded.entity_registry.len(), 2);
+        assert_eq!(
+            loaded.get_field().cells().count(),
+            scene.get_field().cells().count()
+        );
     }
 }
@@ -0,0 +1,209 @@
+//! Shared fixtures for the engine's unit tests
+// Not every fixture is used under every combination of features
+#![allow(dead_code)]
+
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use graphics::Context;
+use opengl_graphics::{GlGraphics, OpenGL};
+use piston::{RenderArgs, WindowSettings};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    error::RenderResult,
+    gameobject::{
+        entity::{
+            registry::{EntityHandle, RegisteredEntity},
+            Entity, HasEntity,
+        },
+        field::{Cell, CellColorView, Field, FieldColorView},
+        NO_SOLIDITY, SOLID,
+    },
+    rendering::{registry::TextureRegistry, Camera},
+    scene::{GameTickArgs, Scene, SpawnArgs},
+    types::{
+        physbox::{HasBoxMut, PhysBox},
+        HasUuid,
+    },
+    GlutinWindow, ScarabError, ScarabResult,
+};
+
+/// A bare registered entity that doesn't render anything
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestEntity {
+    pub entity: Entity,
+    /// The number of times `on_spawn` has run
+    pub spawned: usize,
+    /// The number of times `on_despawn` has run
+    pub despawned: usize,
+    /// Entities to spawn on the next game tick
+    pub to_spawn: Vec<TestEntity>,
+    /// The layer the entity is drawn on
+    pub layer: i32,
+    /// Makes `on_spawn` and `on_despawn` fail after counting the call
+    pub fail_hooks: bool,
+}
+
+impl TestEntity {
+    /// A default entity with its box at the given `[x, y, w, h]`
+    pub fn at(physbox: [f64; 4]) -> Self {
+        let mut entity = Entity::new().unwrap();
+        entity
+            .get_box_mut()
+            .set_pos([physbox[0], physbox[1]].into());
+        entity
+            .get_box_mut()
+            .set_size([physbox[2], physbox[3]].into())
+            .unwrap();
+        Self {
+            entity,
+            spawned: 0,
+            despawned: 0,
+            to_spawn: Vec::new(),
+            layer: 0,
+            fail_hooks: false,
+        }
+    }
+}
+
+impl HasUuid for TestEntity {
+    fn uuid(&self) -> Uuid {
+        self.entity.uuid()
+    }
+}
+
+impl HasEntity for TestEntity {
+    fn get_entity(&self) -> &Entity {
+        &self.entity
+    }
+
+    fn get_entity_mut(&mut self) -> &mut Entity {
+        &mut self.entity
+    }
+}
+
+impl RegisteredEntity for TestEntity {
+    type Player = TestEntity;
+
+    fn inner_entity(&self) -> &Entity {
+        &self.entity
+    }
+
+    fn inner_entity_mut(&mut self) -> &mut Entity {
+        &mut self.entity
+    }
+
+    fn maybe_player(&self) -> Option<&Self::Player> {
+        None
+    }
+
+    fn maybe_player_mut(&mut self) -> Option<&mut Self::Player> {
+        None
+    }
+
+    fn game_tick(
+        &mut self,
+        _this: EntityHandle,
+        args: &mut GameTickArgs<Self>,
+    ) -> ScarabResult<()> {
+        args.spawn_queue.append(&mut self.to_spawn);
+        self.entity.game_tick(args)?;
+        Ok(())
+    }
+
+    fn get_layer(&self) -> i32 {
+        self.layer
+    }
+
+    fn on_spawn(&mut self, _args: &SpawnArgs) -> ScarabResult<()> {
+        self.spawned += 1;
+        if self.fail_hooks {
+            return Err(ScarabError::RawString("failed to spawn".to_string()));
+        }
+        Ok(())
+    }
+
+    fn on_despawn(&mut self, _args: &SpawnArgs) -> ScarabResult<()> {
+        self.despawned += 1;
+        if self.fail_hooks {
+            return Err(ScarabError::RawString("failed to despawn".to_string()));
+        }
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        _args: &RenderArgs,
+        _camera: &Camera,
+        _ctx: Context,
+        _texture_registry: &TextureRegistry,
+        _gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        Ok(())
+    }
+}
+
+/// A 100x100 open field split into a left and right half, with a solid floor below it
+pub(crate) fn create_test_field() -> Field {
+    Field::new(vec![
+        Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 50.0, 100.0]).unwrap()),
+        Cell::new(NO_SOLIDITY, PhysBox::new([50.0, 0.0, 50.0, 100.0]).unwrap()),
+        Cell::new(SOLID, PhysBox::new([0.0, 100.0, 100.0, 10.0]).unwrap()),
+    ])
+    .unwrap()
+}
+
+/// An empty scene over [create_test_field]
+pub(crate) fn create_test_scene() -> Scene<TestEntity, FieldColorView> {
+    Scene::new(create_test_field(), create_test_field_view())
+}
+
+/// An empty scene over [create_test_field] whose random number generator is seeded by `seed`
+pub(crate) fn create_seeded_test_scene(seed: u64) -> Scene<TestEntity, FieldColorView> {
+    Scene::with_seed(create_test_field(), create_test_field_view(), seed)
+}
+
+fn create_test_field_view() -> FieldColorView {
+    FieldColorView {
+        solid_view: CellColorView {
+            color: [0.0, 0.0, 0.0, 1.0],
+        },
+        air_view: CellColorView {
+            color: [1.0, 1.0, 1.0, 1.0],
+        },
+        default_view: CellColorView {
+            color: [0.5, 0.5, 0.5, 1.0],
+        },
+    }
+}
+
+/// Loading textures needs a GL context, which lasts for as long as the window does
+pub(crate) fn gl_window() -> GlutinWindow {
+    WindowSettings::new("scarab test", [64, 64])
+        .graphics_api(OpenGL::V3_2)
+        .build()
+        .unwrap()
+}
+
+/// Writes a blank `width` by `height` PNG to `path`
+pub(crate) fn write_png(path: &PathBuf, width: u32, height: u32) {
+    let file = File::create(path).unwrap();
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer
+        .write_image_data(&vec![255; (width * height * 4) as usize])
+        .unwrap();
+}
+
+/// A fresh assets folder holding a 1x1 "default.png" and a 2x2 "sprite.png"
+pub(crate) fn create_test_assets(name: &str) -> PathBuf {
+    let assets_path = std::env::temp_dir().join(format!("scarab-assets-{name}"));
+    let _ = std::fs::remove_dir_all(&assets_path);
+    std::fs::create_dir_all(&assets_path).unwrap();
+    write_png(&assets_path.join("default.png"), 1, 1);
+    write_png(&assets_path.join("sprite.png"), 2, 2);
+    assets_path
+}
@@ -38,6 +38,20 @@ pub enum Axis {
 }
 
 impl Axis {
+    /// An iterator over both axes
+    pub fn iter() -> core::slice::Iter<'static, Axis> {
+        static AXES: [Axis; 2] = [Axis::X, Axis::Y];
+        AXES.iter()
+    }
+
+    /// The axis perpendicular to this one
+    pub fn other(self) -> Axis {
+        match self {
+            Axis::X => Axis::Y,
+            Axis::Y => Axis::X,
+        }
+    }
+
     /// Gets the component of the given point along this axis
     pub fn component_of_point(&self, point: &Point) -> Scalar {
         match self {
@@ -45,6 +59,30 @@ impl Axis {
             Axis::Y => point.y,
         }
     }
+
+    /// Gets the component of the given velocity along this axis
+    pub fn component_of_velocity(&self, velocity: &Velocity) -> Scalar {
+        match self {
+            Axis::X => velocity.x,
+            Axis::Y => velocity.y,
+        }
+    }
+
+    /// Sets the component of the given point along this axis, leaving the other component unchanged
+    pub fn set_component_of_point(&self, point: &mut Point, value: Scalar) {
+        match self {
+            Axis::X => point.x = value,
+            Axis::Y => point.y = value,
+        }
+    }
+
+    /// Sets the component of the given velocity along this axis, leaving the other component unchanged
+    pub fn set_component_of_velocity(&self, velocity: &mut Velocity, value: Scalar) {
+        match self {
+            Axis::X => velocity.x = value,
+            Axis::Y => velocity.y = value,
+        }
+    }
 }
 
 impl BoxEdge {
@@ -69,6 +107,27 @@ impl BoxEdge {
         }
     }
 
+    /// The edge whose [normal vector](BoxEdge::normal_vector) points most closely along `v`.
+    /// Returns `None` for the zero vector and exact diagonals, which don't favor any edge
+    pub fn from_normal(v: [Scalar; 2]) -> Option<BoxEdge> {
+        let [x, y] = v;
+        if x.abs() > y.abs() {
+            Some(if x > 0.0 {
+                BoxEdge::Right
+            } else {
+                BoxEdge::Left
+            })
+        } else if y.abs() > x.abs() {
+            Some(if y > 0.0 {
+                BoxEdge::Bottom
+            } else {
+                BoxEdge::Top
+            })
+        } else {
+            None
+        }
+    }
+
     /// The axis that runs perpendicular to this edge
     pub fn perpendicular_axis(&self) -> Axis {
         match self {
@@ -92,7 +151,7 @@ impl BoxEdge {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 /// The velocity of a game object
 pub struct Velocity {
     /// The x component of the velocity
@@ -125,6 +184,29 @@ impl Velocity {
         f64::sqrt(self.x * self.x + self.y * self.y)
     }
 
+    /// The dot product of this velocity and `other`
+    pub fn dot(self, other: Velocity) -> Scalar {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The component of this velocity that runs along `axis`.
+    /// Projecting onto a zero vector gives a zero velocity
+    pub fn project_onto(self, axis: Velocity) -> Velocity {
+        let axis_mag_sq = axis.magnitude_sq();
+        if axis_mag_sq == 0.0 {
+            Self { x: 0.0, y: 0.0 }
+        } else {
+            axis * (self.dot(axis) / axis_mag_sq)
+        }
+    }
+
+    /// This velocity bounced off of a surface with the given normal.
+    /// The normal doesn't need to be a unit vector
+    pub fn reflect(self, normal: Velocity) -> Velocity {
+        let normal = normal.normalize();
+        self - normal * (2.0 * self.dot(normal))
+    }
+
     /// Whether this velocity would be reduced by colliding with a game object on the given edge
     /// i.e. is the dot product of the velocity and the edge's normal negative
     pub fn is_reduced_by_edge(&self, edge: BoxEdge) -> bool {
@@ -201,3 +283,99 @@ pub trait HasUuid {
     /// The object's unique identifier
     fn uuid(&self) -> Uuid;
 }
+
+/// A trait for game objects that move with a velocity
+pub trait HasVelocity {
+    /// The game object's current velocity
+    fn get_velocity(&self) -> Velocity;
+
+    /// Sets the game object's velocity
+    fn set_velocity(&mut self, velocity: Velocity);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn axis_reads_point_and_velocity_components() {
+        let point = Point { x: 1.0, y: 2.0 };
+        let velocity = Velocity { x: 3.0, y: 4.0 };
+
+        assert_eq!(Axis::X.component_of_point(&point), 1.0);
+        assert_eq!(Axis::Y.component_of_point(&point), 2.0);
+        assert_eq!(Axis::X.component_of_velocity(&velocity), 3.0);
+        assert_eq!(Axis::Y.component_of_velocity(&velocity), 4.0);
+    }
+
+    #[test]
+    fn axis_sets_only_its_point_component() {
+        let mut point = Point { x: 1.0, y: 2.0 };
+
+        Axis::X.set_component_of_point(&mut point, 5.0);
+        assert_eq!(point, Point { x: 5.0, y: 2.0 });
+
+        Axis::Y.set_component_of_point(&mut point, -3.0);
+        assert_eq!(point, Point { x: 5.0, y: -3.0 });
+    }
+
+    #[test]
+    fn axis_sets_only_its_velocity_component() {
+        let mut velocity = Velocity { x: 1.0, y: 2.0 };
+
+        Axis::X.set_component_of_velocity(&mut velocity, 5.0);
+        assert_eq!(velocity, Velocity { x: 5.0, y: 2.0 });
+
+        Axis::Y.set_component_of_velocity(&mut velocity, -3.0);
+        assert_eq!(velocity, Velocity { x: 5.0, y: -3.0 });
+    }
+
+    #[test]
+    fn box_edge_from_normal() {
+        for edge in BoxEdge::iter() {
+            assert_eq!(BoxEdge::from_normal(edge.normal_vector()), Some(*edge));
+        }
+        assert_eq!(BoxEdge::from_normal([3.0, -1.0]), Some(BoxEdge::Right));
+        assert_eq!(BoxEdge::from_normal([1.0, 1.0]), None);
+        assert_eq!(BoxEdge::from_normal([-2.0, 2.0]), None);
+        assert_eq!(BoxEdge::from_normal([0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn axis_iterates_and_flips() {
+        let axes: Vec<Axis> = Axis::iter().copied().collect();
+        assert_eq!(axes, vec![Axis::X, Axis::Y]);
+        assert_eq!(Axis::X.other(), Axis::Y);
+        assert_eq!(Axis::Y.other(), Axis::X);
+    }
+
+    #[test]
+    fn velocity_reflects_off_edge_normal() {
+        let velocity = Velocity { x: 1.0, y: -1.0 };
+
+        assert_eq!(
+            velocity.reflect(BoxEdge::Bottom.normal_vector().into()),
+            Velocity { x: 1.0, y: 1.0 }
+        );
+        // Non-unit normals are normalized first
+        assert_eq!(
+            velocity.reflect(Velocity { x: 0.0, y: 5.0 }),
+            Velocity { x: 1.0, y: 1.0 }
+        );
+    }
+
+    #[test]
+    fn velocity_projects_onto_axis() {
+        let velocity = Velocity { x: 3.0, y: 4.0 };
+
+        assert_eq!(velocity.dot(Velocity { x: 2.0, y: 1.0 }), 10.0);
+        assert_eq!(
+            velocity.project_onto(Velocity { x: 2.0, y: 0.0 }),
+            Velocity { x: 3.0, y: 0.0 }
+        );
+        assert_eq!(
+            velocity.project_onto(Velocity { x: 0.0, y: 0.0 }),
+            Velocity { x: 0.0, y: 0.0 }
+        );
+    }
+}
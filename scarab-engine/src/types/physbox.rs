@@ -2,7 +2,7 @@ use graphics::types::{Scalar, Vec2d};
 use serde::{Deserialize, Serialize};
 use shapes::{Point, Size};
 
-use super::{Axis, BoxEdge};
+use super::{Axis, BoxEdge, Velocity};
 use crate::{PhysicsError, PhysicsResult};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -90,7 +90,7 @@ impl PhysBox {
 
     /// Translates self so that the y value of the bottom edge is 'val'
     pub fn set_bottom_y(&mut self, val: Scalar) {
-        self.pos.y = val - self.size.w;
+        self.pos.y = val - self.size.h;
     }
 
     /// Translates self so that the x value of the right edge is 'val'
@@ -98,6 +98,14 @@ impl PhysBox {
         self.pos.x = val - self.size.w;
     }
 
+    /// The point in the middle of self
+    pub fn center(&self) -> Point {
+        Point {
+            x: self.pos.x + self.size.w / 2.0,
+            y: self.pos.y + self.size.h / 2.0,
+        }
+    }
+
     /// Computes the area of self
     pub fn area(&self) -> Scalar {
         self.size.w * self.size.h
@@ -165,8 +173,16 @@ impl PhysBox {
     /// assert_eq!(box2, PhysBox::new([2.0, 5.0, 5.0, 5.0].into()).unwrap());
     /// ```
     pub fn shift_to_nonoverlapping(&mut self, other: &Self) {
+        if let Some(edge) = self.min_penetration_edge(other) {
+            self.set_touching_opposite_edge(other, edge)
+        }
+    }
+
+    /// The edge of `self` that overlaps `other` the least, i.e. the edge to push `self` out along.
+    /// `None` if they don't overlap
+    pub fn min_penetration_edge(&self, other: &Self) -> Option<BoxEdge> {
         if !self.has_overlap(other) {
-            return;
+            return None;
         }
         let diffs = vec![
             (BoxEdge::Top, other.bottom_y() - self.top_y()),
@@ -185,9 +201,7 @@ impl PhysBox {
             }
         });
 
-        if let Some((edge, _diff)) = shift_edge_opt {
-            self.set_touching_opposite_edge(other, *edge)
-        }
+        shift_edge_opt.map(|(edge, _diff)| *edge)
     }
 
     /// Is the pos contained in the box
@@ -235,6 +249,87 @@ impl PhysBox {
             && (other.pos.y < this_bottom_right.y)
     }
 
+    /// A copy of self grown outward by `margin` on every side.
+    /// A negative margin shrinks the box instead, and fails if the box would collapse
+    pub fn expanded(&self, margin: Scalar) -> PhysicsResult<Self> {
+        Self::new([
+            self.pos.x - margin,
+            self.pos.y - margin,
+            self.size.w + 2.0 * margin,
+            self.size.h + 2.0 * margin,
+        ])
+    }
+
+    /// A copy of self grown outward by `margin` on both sides along only the given axis
+    pub fn expanded_axis(&self, axis: Axis, margin: Scalar) -> PhysicsResult<Self> {
+        match axis {
+            Axis::X => Self::new([
+                self.pos.x - margin,
+                self.pos.y,
+                self.size.w + 2.0 * margin,
+                self.size.h,
+            ]),
+            Axis::Y => Self::new([
+                self.pos.x,
+                self.pos.y - margin,
+                self.size.w,
+                self.size.h + 2.0 * margin,
+            ]),
+        }
+    }
+
+    /// The region where `self` and `other` overlap.
+    /// Boxes that only touch along an edge or corner have no intersection, matching [PhysBox::has_overlap]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let left = f64::max(self.left_x(), other.left_x());
+        let top = f64::max(self.top_y(), other.top_y());
+        let right = f64::min(self.right_x(), other.right_x());
+        let bottom = f64::min(self.bottom_y(), other.bottom_y());
+
+        let pos = [left, top].into();
+        let size = [right - left, bottom - top].into();
+        Self::validate(pos, size).ok()?;
+        Some(Self { pos, size })
+    }
+
+    /// The point in (or on the edge of) the box that's closest to `p`.
+    /// Points inside the box are their own closest point
+    pub fn closest_point(&self, p: Point) -> Point {
+        [
+            p.x.clamp(self.left_x(), self.right_x()),
+            p.y.clamp(self.top_y(), self.bottom_y()),
+        ]
+        .into()
+    }
+
+    /// The distance from `p` to the closest point of the box, 0 when `p` is inside it
+    pub fn distance_to_point(&self, p: Point) -> Scalar {
+        let closest = self.closest_point(p);
+        f64::hypot(p.x - closest.x, p.y - closest.y)
+    }
+
+    /// The smallest box that contains both `self` and `other`
+    pub fn union(&self, other: &Self) -> Self {
+        let left = f64::min(self.left_x(), other.left_x());
+        let top = f64::min(self.top_y(), other.top_y());
+        let right = f64::max(self.right_x(), other.right_x());
+        let bottom = f64::max(self.bottom_y(), other.bottom_y());
+
+        // Can't be smaller than either box, so the size is always valid
+        Self {
+            pos: [left, top].into(),
+            size: [right - left, bottom - top].into(),
+        }
+    }
+
+    /// The smallest box containing both this box and this box moved by `displacement`.
+    /// A cheap, conservative stand-in for everything the box passes through while moving
+    pub fn swept_region(&self, displacement: Velocity) -> Self {
+        let mut moved = *self;
+        moved.pos = self.pos + displacement;
+        self.union(&moved)
+    }
+
     /// Is `self` fully contained within `other`
     /// Uses fully inclusive logic so that a.is_fully_contained_by(&a) is true
     /// i.e. in set notation `a.is_fully_contained_by(&b)` means that $a \subset b$
@@ -242,6 +337,37 @@ impl PhysBox {
         other.contains_pos_inclusive(self.pos) && other.contains_pos_inclusive(self.pos + self.size)
     }
 
+    /// Finds where the line segment from `from` to `to` first enters self, as a fraction
+    /// of the way along the segment (0.0 when `from` is inside self).
+    /// Segments that only touch the edges of self don't enter it
+    pub fn segment_entry(&self, from: Point, to: Point) -> Option<Scalar> {
+        let mut t_enter: Scalar = 0.0;
+        let mut t_exit: Scalar = 1.0;
+
+        for axis in [Axis::X, Axis::Y] {
+            let start = axis.component_of_point(&from);
+            let delta = axis.component_of_point(&to) - start;
+            let near = self.get_near_axis(axis);
+            let far = self.get_far_axis(axis);
+
+            if delta == 0.0 {
+                if start <= near || start >= far {
+                    return None;
+                }
+            } else {
+                let t_near = (near - start) / delta;
+                let t_far = (far - start) / delta;
+                t_enter = t_enter.max(t_near.min(t_far));
+                t_exit = t_exit.min(t_near.max(t_far));
+                if t_enter >= t_exit {
+                    return None;
+                }
+            }
+        }
+
+        Some(t_enter)
+    }
+
     /// Returns a list of the edges of `self` that `other` touches.
     /// (todo: test) Will be empty iff `other` is fully contained by `self` or they have no overlap
     pub fn edges_crossed_by(&self, other: &Self) -> Vec<BoxEdge> {
@@ -308,6 +434,12 @@ impl HasBoxMut for PhysBox {
     }
 }
 
+/// The smallest box that contains every box in `boxes`, or `None` if there are none
+pub fn bounding_box(boxes: &[PhysBox]) -> Option<PhysBox> {
+    let (first, rest) = boxes.split_first()?;
+    Some(rest.iter().fold(*first, |bounds, b| bounds.union(b)))
+}
+
 /// A trait for game objects that wrap a physbox
 pub trait HasBox {
     /// A reference to the game object's PhysBox
@@ -326,6 +458,82 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn distance_to_point_inside_and_outside() {
+        let physbox = PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap();
+
+        let inside: Point = [3.0, 7.0].into();
+        assert_eq!(physbox.closest_point(inside), inside);
+        assert_eq!(physbox.distance_to_point(inside), 0.0);
+
+        // Off the right edge
+        let right: Point = [15.0, 4.0].into();
+        assert_eq!(physbox.closest_point(right), [10.0, 4.0].into());
+        assert_eq!(physbox.distance_to_point(right), 5.0);
+
+        // Off the top left corner
+        let corner: Point = [-3.0, -4.0].into();
+        assert_eq!(physbox.closest_point(corner), [0.0, 0.0].into());
+        assert_eq!(physbox.distance_to_point(corner), 5.0);
+    }
+
+    #[test]
+    fn union_of_overlapping_boxes() {
+        let a = PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap();
+        let b = PhysBox::new([5.0, -5.0, 10.0, 10.0]).unwrap();
+        let expected = PhysBox::new([0.0, -5.0, 15.0, 15.0]).unwrap();
+
+        assert_eq!(a.union(&b), expected);
+        assert_eq!(b.union(&a), expected);
+        assert_eq!(a.union(&a), a);
+    }
+
+    #[test]
+    fn swept_region_covers_both_ends() {
+        let start = PhysBox::new([10.0, 10.0, 2.0, 4.0]).unwrap();
+        for (displacement, expected) in [
+            ([5.0, 0.0], [10.0, 10.0, 7.0, 4.0]),
+            ([0.0, -6.0], [10.0, 4.0, 2.0, 10.0]),
+            ([-3.0, 2.0], [7.0, 10.0, 5.0, 6.0]),
+        ] {
+            let displacement: Velocity = displacement.into();
+            let swept = start.swept_region(displacement);
+            assert_eq!(swept, PhysBox::new(expected).unwrap());
+
+            let mut end = start;
+            end.set_pos(*start.pos() + displacement);
+            assert!(start.is_fully_contained_by(&swept));
+            assert!(end.is_fully_contained_by(&swept));
+        }
+        assert_eq!(start.swept_region([0.0, 0.0].into()), start);
+    }
+
+    #[test]
+    fn union_of_disjoint_boxes() {
+        let a = PhysBox::new([0.0, 0.0, 2.0, 2.0]).unwrap();
+        let b = PhysBox::new([10.0, 20.0, 5.0, 5.0]).unwrap();
+
+        assert_eq!(a.union(&b), PhysBox::new([0.0, 0.0, 15.0, 25.0]).unwrap());
+    }
+
+    #[test]
+    fn bounding_box_of_slice() {
+        assert_eq!(bounding_box(&[]), None);
+
+        let a = PhysBox::new([0.0, 0.0, 2.0, 2.0]).unwrap();
+        assert_eq!(bounding_box(&[a]), Some(a));
+
+        let boxes = [
+            a,
+            PhysBox::new([-3.0, 1.0, 1.0, 1.0]).unwrap(),
+            PhysBox::new([4.0, 6.0, 1.0, 2.0]).unwrap(),
+        ];
+        assert_eq!(
+            bounding_box(&boxes),
+            Some(PhysBox::new([-3.0, 0.0, 8.0, 8.0]).unwrap())
+        );
+    }
+
     #[test]
     fn negative_sized_rect_not_allowed() {
         assert_eq!(
@@ -469,6 +677,98 @@ mod test {
         assert!(!physbox1_1.has_overlap(&physbox1_0));
     }
 
+    #[test]
+    fn set_bottom_y_uses_height() {
+        let mut physbox = PhysBox::new([0.0, 0.0, 3.0, 7.0]).unwrap();
+        physbox.set_bottom_y(10.0);
+
+        assert_eq!(physbox.pos().y, 3.0);
+        assert_eq!(physbox.bottom_y(), 10.0);
+    }
+
+    #[test]
+    fn set_right_x_uses_width() {
+        let mut physbox = PhysBox::new([0.0, 0.0, 3.0, 7.0]).unwrap();
+        physbox.set_right_x(10.0);
+
+        assert_eq!(physbox.pos().x, 7.0);
+        assert_eq!(physbox.right_x(), 10.0);
+    }
+
+    #[test]
+    fn expanded_grows_every_side() {
+        let physbox = PhysBox::new([2.0, 3.0, 4.0, 5.0]).unwrap();
+
+        assert_eq!(
+            physbox.expanded(1.0).unwrap(),
+            PhysBox::new([1.0, 2.0, 6.0, 7.0]).unwrap()
+        );
+        assert_eq!(
+            physbox.expanded(-1.0).unwrap(),
+            PhysBox::new([3.0, 4.0, 2.0, 3.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn expanded_axis_grows_one_axis() {
+        let physbox = PhysBox::new([2.0, 3.0, 4.0, 5.0]).unwrap();
+
+        assert_eq!(
+            physbox.expanded_axis(Axis::X, 1.0).unwrap(),
+            PhysBox::new([1.0, 3.0, 6.0, 5.0]).unwrap()
+        );
+        assert_eq!(
+            physbox.expanded_axis(Axis::Y, 1.0).unwrap(),
+            PhysBox::new([2.0, 2.0, 4.0, 7.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn expanded_by_collapsing_margin_fails() {
+        let physbox = PhysBox::new([2.0, 3.0, 4.0, 5.0]).unwrap();
+
+        assert_eq!(physbox.expanded(-2.0), Err(PhysicsError::PhysBoxSize));
+        assert_eq!(
+            physbox.expanded_axis(Axis::X, -2.0),
+            Err(PhysicsError::PhysBoxSize)
+        );
+        // Only the x-axis collapses, so the y-axis can still shrink
+        assert!(physbox.expanded_axis(Axis::Y, -2.0).is_ok());
+    }
+
+    #[test]
+    fn intersection_of_contained_box_is_inner_box() {
+        let outer = PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap();
+        let inner = PhysBox::new([2.0, 3.0, 4.0, 5.0]).unwrap();
+
+        assert_eq!(outer.intersection(&inner), Some(inner));
+        assert_eq!(inner.intersection(&outer), Some(inner));
+    }
+
+    #[test]
+    fn intersection_of_corner_overlap() {
+        let physbox1 = PhysBox::new([0.0, 0.0, 4.0, 4.0]).unwrap();
+        let physbox2 = PhysBox::new([3.0, 2.0, 4.0, 4.0]).unwrap();
+        let expected = PhysBox::new([3.0, 2.0, 1.0, 2.0]).unwrap();
+
+        assert_eq!(physbox1.intersection(&physbox2), Some(expected));
+        assert_eq!(physbox2.intersection(&physbox1), Some(expected));
+    }
+
+    #[test]
+    fn intersection_of_touching_boxes_is_none() {
+        let physbox = PhysBox::new([0.0, 0.0, 5.0, 5.0]).unwrap();
+        let right = PhysBox::new([5.0, 0.0, 5.0, 5.0]).unwrap();
+        let below = PhysBox::new([0.0, 5.0, 5.0, 5.0]).unwrap();
+        let corner = PhysBox::new([5.0, 5.0, 5.0, 5.0]).unwrap();
+        let apart = PhysBox::new([20.0, 20.0, 5.0, 5.0]).unwrap();
+
+        for other in [right, below, corner, apart] {
+            assert_eq!(physbox.intersection(&other), None);
+            assert_eq!(other.intersection(&physbox), None);
+        }
+    }
+
     #[test]
     fn box_contains_itself() {
         let physbox = PhysBox::new([1.0, 50.0, 20.0, 20.0]).unwrap();
@@ -508,4 +808,33 @@ mod test {
         assert!(physbox3.is_fully_contained_by(&physbox1));
         assert!(physbox4.is_fully_contained_by(&physbox1));
     }
+
+    #[test]
+    fn segment_entry_finds_entry_fraction() {
+        let physbox = PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap();
+
+        assert_eq!(
+            physbox.segment_entry([0.0, 5.0].into(), [20.0, 5.0].into()),
+            Some(0.5)
+        );
+        assert_eq!(
+            physbox.segment_entry([15.0, 5.0].into(), [30.0, 5.0].into()),
+            Some(0.0)
+        );
+        // Stops short of the box
+        assert_eq!(
+            physbox.segment_entry([0.0, 5.0].into(), [5.0, 5.0].into()),
+            None
+        );
+        // Runs along the top edge
+        assert_eq!(
+            physbox.segment_entry([0.0, 0.0].into(), [30.0, 0.0].into()),
+            None
+        );
+        // Passes the box diagonally
+        assert_eq!(
+            physbox.segment_entry([0.0, 0.0].into(), [10.0, 10.0].into()),
+            None
+        );
+    }
 }
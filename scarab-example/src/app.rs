@@ -1,4 +1,4 @@
-use std::{fmt::Debug, fs::File, io::Write};
+use std::{fmt::Debug, fs::File};
 
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlGraphics, OpenGL};
@@ -11,13 +11,14 @@ use scarab_engine::{
         field::Field,
     },
     input::InputRegistry,
+    persistence::{read_versioned, write_versioned},
     rendering::{
         debug::DebugView,
         registry::{TextureList, TextureRegistry},
         Camera, View,
     },
     scene::Scene,
-    App, ScarabError, ScarabResult,
+    App, ScarabResult,
 };
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -25,7 +26,7 @@ use serde::{Deserialize, Serialize};
 use crate::{debug::DebugOptions, external_serde::EventSettingsDef};
 
 /// A semver-like version of the AppData's save format
-static SAVE_VERSION: &'static str = "0.1.0";
+static SAVE_VERSION: &'static str = "0.3.0";
 
 pub struct ExampleApp<E, V, I, J, D> {
     gl: GlGraphics, // OpenGL drawing backend.
@@ -52,7 +53,6 @@ impl<E, V, I, J, D> ExampleApp<E, V, I, J, D> {
             gl,
             window,
             data: AppData {
-                save_version: SAVE_VERSION.to_string(),
                 scene,
                 camera,
                 game_input_registry,
@@ -86,17 +86,9 @@ where
             .build()
             .unwrap(); // TODO: don't panic here
 
-        let file = File::open(&save_name).map_err(|e| ScarabError::RawString(format!("{:}", e)))?;
-        let app_data: AppData<E, V, I, J, D> = rmp_serde::from_read(file)
-            .map_err(|e| ScarabError::RawString(format!("Could not parse file: {:?}", e)))?;
-
+        let file = File::open(&save_name)?;
         // Lazy version checking requires exact match.
-        if app_data.save_version != SAVE_VERSION {
-            return Err(ScarabError::RawString(format!(
-                "Save version mismatch: save has {:}, needs {SAVE_VERSION:}",
-                app_data.save_version
-            )));
-        }
+        let app_data: AppData<E, V, I, J, D> = read_versioned(file, SAVE_VERSION)?;
 
         let texture_registry = app_data.texture_list.clone().try_into()?;
 
@@ -148,6 +140,7 @@ where
     }
 
     fn update(&mut self, args: &UpdateArgs) {
+        self.data.camera.update(args.dt, self.data.scene.rng_mut());
         let _ = self
             .data
             .scene
@@ -160,14 +153,8 @@ where
     }
 
     fn close(self: Box<Self>, _args: &CloseArgs) {
-        let mut f = File::create(&self.save_name).unwrap();
-        let mut buf = Vec::new();
-        AppData::from(self)
-            .serialize(&mut rmp_serde::Serializer::new(&mut buf))
-            .unwrap();
-
-        let _ = f
-            .write_all(&buf)
+        let f = File::create(&self.save_name).unwrap();
+        let _ = write_versioned(f, SAVE_VERSION, &AppData::from(self))
             .map_err(|e| println!("Saving app state failed with error: {e}"));
     }
 
@@ -197,7 +184,6 @@ where
 /// i.e. scenes, controls, rendering settings
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppData<E, V, I, J, D> {
-    save_version: String,
     scene: Scene<E, V>,
     camera: Camera,
     game_input_registry: I,
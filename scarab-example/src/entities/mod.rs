@@ -8,7 +8,7 @@ use scarab_engine::{
     error::RenderResult,
     gameobject::{
         entity::{
-            registry::{RegisteredDebugEntity, RegisteredEntity},
+            registry::{EntityHandle, RegisteredDebugEntity, RegisteredEntity},
             Entity, HasEntity,
         },
         HasHealth,
@@ -113,12 +113,14 @@ impl RegisteredEntity for ExampleEntities {
         }
     }
 
-    fn game_tick(&mut self, this_idx: usize, args: &mut GameTickArgs<Self>) -> ScarabResult<()> {
+    fn game_tick(&mut self, this: EntityHandle, args: &mut GameTickArgs<Self>) -> ScarabResult<()> {
         match self {
-            ExampleEntities::Player((player, _)) => player.game_tick(this_idx, args),
-            ExampleEntities::Enemy((enemy, _)) => {
-                enemy.entity.game_tick(args).map_err(|e| e.into())
-            }
+            ExampleEntities::Player((player, _)) => player.game_tick(this, args),
+            ExampleEntities::Enemy((enemy, _)) => enemy
+                .entity
+                .game_tick(args)
+                .map(|_| ())
+                .map_err(|e| e.into()),
         }
     }
 }
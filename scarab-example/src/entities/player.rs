@@ -1,7 +1,8 @@
 use graphics::types::Color;
 use scarab_engine::{
     gameobject::entity::{
-        effect_helpers::{BasicAttack, Cooldown, TryAction},
+        effect_helpers::{AbilitySlot, BasicAttack},
+        registry::EntityHandle,
         Entity, HasEntity,
     },
     rendering::{
@@ -26,45 +27,39 @@ use crate::debug::DebugOptions;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Player {
     pub entity: Entity,
-    // TODO! this needs to be a proper struct a tuple is just horrendous
-    attack: (TryAction, BasicAttack, f64),
+    attack: AbilitySlot,
 }
 
 impl Player {
     pub fn new(entity: Entity, damage: f64, cooldown: f64) -> Self {
         Self {
             entity,
-            attack: (TryAction::default(), BasicAttack::new(damage), cooldown),
+            attack: AbilitySlot::new(BasicAttack::new(damage), cooldown),
         }
     }
 
     pub fn attack(&mut self) {
-        self.attack.0.maybe_set_doing();
+        self.attack.request();
     }
 
     pub fn game_tick(
         &mut self,
-        this_idx: usize,
+        this: EntityHandle,
         args: &mut GameTickArgs<ExampleEntities>,
     ) -> ScarabResult<()> {
         self.entity.game_tick(args)?;
 
-        self.attack.0.cooldown.cool(args.dt);
-
-        if self.attack.0.should_do(Cooldown::Cooling(self.attack.2)) {
-            let mut target_area = self.entity.get_box().clone();
-            let size = self.entity.get_box().size();
-            let _ = target_area.set_size([size.w * 2.0, size.h * 2.0].into());
-            target_area.set_pos(*self.entity.get_box().pos() - Point::from([size.w, size.h]));
-            args.pending_attacks
-                .push(self.attack.1.into_pending_effect(this_idx, target_area));
-        }
+        let mut target_area = self.entity.get_box().clone();
+        let size = self.entity.get_box().size();
+        let _ = target_area.set_size([size.w * 2.0, size.h * 2.0].into());
+        target_area.set_pos(*self.entity.get_box().pos() - Point::from([size.w, size.h]));
+        self.attack.try_use(this, target_area, args);
 
         Ok(())
     }
 
     pub fn cooldown_fraction(&self) -> f64 {
-        f64::from(self.attack.0.cooldown) / self.attack.2
+        self.attack.cooldown_fraction()
     }
 }
 
@@ -90,7 +90,7 @@ fn main() -> ScarabResult<()> {
     let cambox = PhysBox::new([0.0, 0.0, camera_size[0].into(), camera_size[1].into()])?;
     let camera = Camera::new(cambox, window.size().into());
 
-    let texture_registry = TextureRegistry::new(
+    let texture_registry = TextureRegistry::new_nearest(
         // This ends up being the path from cwd to the assets. It has to change depending on deployment
         "scarab-example/assets".into(),
         "texture-default.png".into(),